@@ -216,12 +216,12 @@ macro_rules! builder_into {
             $crate::builder::FlexStrBuilder::InlineBuffer(_) => {
                 if $buffer.is_inline_candidate() {
                     let len = $buffer.len() as u8;
-                    $crate::FlexStr::from_inline(
-                        $crate::storage::inline::InlineFlexStr::from_array(
-                            $buffer.into_inner(),
-                            len,
-                        ),
-                    )
+                    let data = $buffer.into_inner();
+                    // SAFETY: The buffer is an inline candidate, so `len` fits and the first `len`
+                    // bytes were initialized with valid UTF-8 by the builder
+                    let s =
+                        unsafe { $crate::storage::inline::InlineFlexStr::from_array(data, len) };
+                    $crate::FlexStr::from_inline(s)
                 } else {
                     $crate::traits::ToFlex::to_flex(&*$buffer)
                 }
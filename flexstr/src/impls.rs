@@ -1,4 +1,10 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
 use alloc::string::String;
+use alloc::sync::Arc;
+#[cfg(feature = "serde")]
+use alloc::vec::Vec;
 use core::borrow::Borrow;
 use core::cmp::Ordering;
 use core::convert::Infallible;
@@ -10,14 +16,19 @@ use core::marker::PhantomData;
 use core::ops::{
     Add, Deref, Index, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive,
 };
+use core::ptr;
 use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::ffi::OsStr;
+#[cfg(feature = "std")]
+use std::path::Path;
 
 #[cfg(feature = "serde")]
-use serde::de::{Error, Visitor};
+use serde::de::{Error, Unexpected, Visitor};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::{from_iter_char, from_iter_str, FlexStr, IntoFlex};
+use crate::{from_iter_char, from_iter_owned, from_iter_str, FlexStr, IntoChars, IntoFlex};
 
 // *** Debug / Display ***
 
@@ -82,6 +93,19 @@ impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> Hash
 where
     HEAP: Deref<Target = str>,
 {
+    /// Hashes identically to the equivalent [str] regardless of storage or `HEAP` type. Combined
+    /// with the [Borrow] implementation, this means any string type can look up keys in a map of
+    /// any [FlexStr] type via [as_str](FlexStr::as_str), without allocating or converting.
+    /// ```
+    /// use std::collections::HashMap;
+    /// use flexstr::{LocalStr, SharedStr};
+    ///
+    /// let mut map: HashMap<LocalStr, i32> = HashMap::new();
+    /// map.insert("This is too long to be inlined!!!!".into(), 1);
+    ///
+    /// let key = SharedStr::from_ref("This is too long to be inlined!!!!");
+    /// assert_eq!(map.get(key.as_str()), Some(&1));
+    /// ```
     #[inline]
     fn hash<H: Hasher>(&self, state: &mut H) {
         str::hash(self, state)
@@ -101,10 +125,17 @@ where
     /// let s: LocalStr = lit.into();
     /// let s2: SharedStr = lit.into();
     /// assert_eq!(s, s2);
+    ///
+    /// let long: LocalStr = "This is too long to be inlined!!!!".into();
+    /// assert_eq!(long, long.clone());
     /// ```
     #[inline]
     fn eq(&self, other: &FlexStr<SIZE, PAD1, PAD2, HEAP2>) -> bool {
-        str::eq(self, &**other)
+        let (s1, s2): (&str, &str) = (self, other);
+        // Clones of heap and static strings share the same memory, so there is no need to compare
+        // the contents. Otherwise, `str` equality compares lengths and then uses `memcmp`, which is
+        // already vectorized on all major platforms
+        ptr::eq(s1, s2) || s1 == s2
     }
 }
 
@@ -124,7 +155,7 @@ where
     /// ```
     #[inline]
     fn eq(&self, other: &FlexStr<SIZE, PAD1, PAD2, HEAP2>) -> bool {
-        str::eq(self, &**other)
+        FlexStr::eq(*self, other)
     }
 }
 
@@ -182,6 +213,89 @@ where
     }
 }
 
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
+    PartialEq<FlexStr<SIZE, PAD1, PAD2, HEAP>> for &str
+where
+    HEAP: Deref<Target = str>,
+{
+    /// ```
+    /// use flexstr::{LocalStr, ToFlex};
+    ///
+    /// let lit = "inlined";
+    /// let s: LocalStr = lit.to_flex();
+    /// assert_eq!(lit, s);
+    ///
+    /// // Optional strings can be compared by dereferencing to `Option<&str>`
+    /// let opt: Option<LocalStr> = Some(s);
+    /// assert_eq!(opt.as_deref(), Some(lit));
+    /// ```
+    #[inline]
+    fn eq(&self, other: &FlexStr<SIZE, PAD1, PAD2, HEAP>) -> bool {
+        str::eq(self, &**other)
+    }
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
+    PartialEq<FlexStr<SIZE, PAD1, PAD2, HEAP>> for str
+where
+    HEAP: Deref<Target = str>,
+{
+    /// ```
+    /// use flexstr::{LocalStr, ToFlex};
+    ///
+    /// let lit = "inlined";
+    /// let s: LocalStr = lit.to_flex();
+    /// assert!(*lit == s);
+    /// ```
+    #[inline]
+    fn eq(&self, other: &FlexStr<SIZE, PAD1, PAD2, HEAP>) -> bool {
+        str::eq(self, &**other)
+    }
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
+    PartialEq<FlexStr<SIZE, PAD1, PAD2, HEAP>> for String
+where
+    HEAP: Deref<Target = str>,
+{
+    /// ```
+    /// use flexstr::LocalStr;
+    ///
+    /// let lit = "inlined";
+    /// let s: LocalStr = lit.into();
+    /// assert_eq!(lit.to_string(), s);
+    /// ```
+    #[inline]
+    fn eq(&self, other: &FlexStr<SIZE, PAD1, PAD2, HEAP>) -> bool {
+        str::eq(self, &**other)
+    }
+}
+
+macro_rules! impl_eq_ref_count {
+    ($($module:ident::$heap:ident => $alias:ident),+) => {
+        $(impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> PartialEq<$heap<str>>
+            for FlexStr<SIZE, PAD1, PAD2, HEAP>
+        where
+            HEAP: Deref<Target = str>,
+        {
+            /// ```
+            #[doc = concat!("use std::", stringify!($module), "::", stringify!($heap), ";")]
+            #[doc = concat!("use flexstr::", stringify!($alias), ";")]
+            ///
+            /// let lit = "inlined";
+            #[doc = concat!("let s: ", stringify!($alias), " = lit.into();")]
+            #[doc = concat!("assert_eq!(s, ", stringify!($heap), "::<str>::from(lit));")]
+            /// ```
+            #[inline]
+            fn eq(&self, other: &$heap<str>) -> bool {
+                str::eq(self, &**other)
+            }
+        })+
+    };
+}
+
+impl_eq_ref_count!(rc::Rc => LocalStr, sync::Arc => SharedStr);
+
 impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> Eq
     for FlexStr<SIZE, PAD1, PAD2, HEAP>
 where
@@ -198,7 +312,7 @@ where
 {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        str::partial_cmp(self, other)
+        Some(self.cmp(other))
     }
 }
 
@@ -235,6 +349,72 @@ where
     }
 }
 
+macro_rules! impl_cmp_std_str {
+    ($([$($lt:lifetime)?] $type:ty => $example:literal),+) => {
+        $(impl<$($lt,)? const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> PartialEq<$type>
+            for FlexStr<SIZE, PAD1, PAD2, HEAP>
+        where
+            HEAP: Deref<Target = str>,
+        {
+            /// ```
+            #[doc = $example]
+            /// ```
+            #[inline]
+            fn eq(&self, other: &$type) -> bool {
+                str::eq(self, &**other)
+            }
+        }
+
+        impl<$($lt,)? const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
+            PartialEq<FlexStr<SIZE, PAD1, PAD2, HEAP>> for $type
+        where
+            HEAP: Deref<Target = str>,
+        {
+            #[inline]
+            fn eq(&self, other: &FlexStr<SIZE, PAD1, PAD2, HEAP>) -> bool {
+                str::eq(self, &**other)
+            }
+        }
+
+        impl<$($lt,)? const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> PartialOrd<$type>
+            for FlexStr<SIZE, PAD1, PAD2, HEAP>
+        where
+            HEAP: Deref<Target = str>,
+        {
+            #[inline]
+            fn partial_cmp(&self, other: &$type) -> Option<Ordering> {
+                str::partial_cmp(self, other)
+            }
+        }
+
+        impl<$($lt,)? const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
+            PartialOrd<FlexStr<SIZE, PAD1, PAD2, HEAP>> for $type
+        where
+            HEAP: Deref<Target = str>,
+        {
+            #[inline]
+            fn partial_cmp(&self, other: &FlexStr<SIZE, PAD1, PAD2, HEAP>) -> Option<Ordering> {
+                str::partial_cmp(self, other)
+            }
+        })+
+    };
+}
+
+impl_cmp_std_str!(
+    ['a] Cow<'a, str> => "use std::borrow::Cow;
+use flexstr::local_str;
+
+let s = local_str!(\"test\");
+assert_eq!(s, Cow::Borrowed(\"test\"));
+assert_eq!(Cow::<str>::Owned(\"test\".to_string()), s);
+assert!(s < Cow::Borrowed(\"zzz\"));",
+    [] Box<str> => "use flexstr::local_str;
+
+let s = local_str!(\"test\");
+assert_eq!(s, Box::<str>::from(\"test\"));
+assert!(Box::<str>::from(\"zzz\") > s);"
+);
+
 // *** Index ***
 
 macro_rules! impl_ranges {
@@ -312,6 +492,45 @@ where
     }
 }
 
+#[cfg(feature = "std")]
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> AsRef<Path>
+    for FlexStr<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    /// ```
+    /// use flexstr::local_str;
+    /// use std::path::Path;
+    ///
+    /// let s = local_str!("/tmp/file.txt");
+    /// assert_eq!(Path::new("/tmp").join(&s), Path::new("/tmp/file.txt"));
+    /// ```
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> AsRef<OsStr>
+    for FlexStr<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    /// ```
+    /// use flexstr::local_str;
+    /// use std::ffi::OsStr;
+    ///
+    /// let s = local_str!("value");
+    /// let os: &OsStr = s.as_ref();
+    /// assert_eq!(os, "value");
+    /// ```
+    #[inline]
+    fn as_ref(&self) -> &OsStr {
+        self.as_os_str()
+    }
+}
+
 impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> Default
     for FlexStr<SIZE, PAD1, PAD2, HEAP>
 {
@@ -433,6 +652,32 @@ where
     }
 }
 
+macro_rules! impl_from_ref_count {
+    ($($module:ident::$heap:ident => $alias:ident),+) => {
+        $(impl<const SIZE: usize, const PAD1: usize, const PAD2: usize> From<$heap<str>>
+            for FlexStr<SIZE, PAD1, PAD2, $heap<str>>
+        {
+            /// Wraps the existing heap string without copying it (equivalent to
+            /// [from_heap](FlexStr::from_heap))
+            /// ```
+            #[doc = concat!("use std::", stringify!($module), "::", stringify!($heap), ";")]
+            #[doc = concat!("use flexstr::", stringify!($alias), ";")]
+            ///
+            #[doc = concat!("let heap: ", stringify!($heap), "<str> = \"heap\".into();")]
+            #[doc = concat!("let s: ", stringify!($alias), " = heap.clone().into();")]
+            /// assert!(s.is_heap());
+            /// assert_eq!(s.as_ptr(), heap.as_ptr());
+            /// ```
+            #[inline]
+            fn from(heap: $heap<str>) -> Self {
+                Self::from_heap(heap)
+            }
+        })+
+    };
+}
+
+impl_from_ref_count!(rc::Rc => LocalStr, sync::Arc => SharedStr);
+
 // *** FromIterator ***
 
 impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP, HEAP2>
@@ -442,16 +687,21 @@ where
     HEAP2: Deref<Target = str>,
 {
     /// ```
-    /// use flexstr::LocalStr;
+    /// use flexstr::{local_str, LocalStr};
     ///
     /// let v: Vec<LocalStr> = vec!["best".into(), "test".into()];
     /// let s: LocalStr = v.into_iter().map(|s| if s == "best" { "test".into() } else { s }).collect();
     /// assert!(s.is_inline());
     /// assert_eq!(s, "testtest");
+    ///
+    /// // A single string is converted directly, so a static string remains static
+    /// let v: Vec<LocalStr> = vec![local_str!("best"), local_str!("test")];
+    /// let s: LocalStr = v.into_iter().filter(|s| s == "test").collect();
+    /// assert!(s.is_static());
     /// ```
     #[inline]
     fn from_iter<I: IntoIterator<Item = FlexStr<SIZE, PAD1, PAD2, HEAP2>>>(iter: I) -> Self {
-        from_iter_str(iter)
+        from_iter_owned(iter, IntoFlex::into_flex)
     }
 }
 
@@ -490,7 +740,7 @@ where
     /// ```
     #[inline]
     fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
-        from_iter_str(iter)
+        from_iter_owned(iter, Self::from)
     }
 }
 
@@ -551,6 +801,35 @@ where
     }
 }
 
+// *** IntoIterator ***
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> IntoIterator
+    for FlexStr<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    type Item = char;
+    type IntoIter = IntoChars<SIZE, PAD1, PAD2, HEAP>;
+
+    /// ```
+    /// use flexstr::LocalStr;
+    ///
+    /// let chars = LocalStr::from_ref("This is too long to be inlined!!!!").into_iter();
+    /// assert_eq!(chars.rev().take(4).collect::<String>(), "!!!!");
+    ///
+    /// let mut count = 0;
+    /// for ch in LocalStr::from("☃☃☃") {
+    ///     assert_eq!(ch, '☃');
+    ///     count += 1;
+    /// }
+    /// assert_eq!(count, 3);
+    /// ```
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        IntoChars::new(self)
+    }
+}
+
 // *** Optional serialization support ***
 
 #[cfg(feature = "serde")]
@@ -602,6 +881,31 @@ where
     {
         Ok(v.into())
     }
+
+    #[inline]
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        match core::str::from_utf8(v) {
+            Ok(s) => Ok(s.into()),
+            Err(_) => Err(Error::invalid_value(Unexpected::Bytes(v), &self)),
+        }
+    }
+
+    #[inline]
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        match String::from_utf8(v) {
+            Ok(s) => Ok(s.into()),
+            Err(err) => Err(Error::invalid_value(
+                Unexpected::Bytes(err.as_bytes()),
+                &self,
+            )),
+        }
+    }
 }
 
 #[cfg(feature = "serde")]
@@ -661,4 +965,19 @@ mod tests {
 
         assert_eq!(&test, &test2);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_bytes() {
+        use crate::LocalStr;
+        use serde::de::value::{BytesDeserializer, Error};
+        use serde::Deserialize;
+
+        let s = LocalStr::deserialize(BytesDeserializer::<Error>::new("☃".as_bytes())).unwrap();
+        assert!(s.is_inline());
+        assert_eq!(s, "☃");
+
+        let bad = BytesDeserializer::<Error>::new(&[0xE2, 0x98]);
+        assert!(LocalStr::deserialize(bad).is_err());
+    }
 }
@@ -83,28 +83,66 @@ pub mod builder;
 #[doc(hidden)]
 mod impls;
 mod macros;
+/// Optional [serde](::serde) helpers
+#[cfg(feature = "serde")]
+pub mod serde;
 #[doc(hidden)]
 pub mod storage;
 #[doc(hidden)]
 pub mod traits;
 
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
 use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(feature = "base64")]
+use base64::engine::general_purpose::STANDARD;
+#[cfg(feature = "base64")]
+use base64::Engine;
+#[cfg(feature = "casefold")]
+use caseless::Caseless;
+use core::cmp::Ordering;
 use core::fmt::{Arguments, Write};
+use core::hash::{Hash, Hasher};
+use core::iter;
+use core::iter::FusedIterator;
 use core::mem;
 use core::mem::ManuallyDrop;
-use core::ops::Deref;
+use core::ops::{Bound, Deref, Range, RangeBounds};
+use core::ptr;
+use core::str::Utf8Error;
+#[cfg(feature = "std")]
+use std::ffi::OsStr;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "search_key")]
+use unicode_normalization::char::is_combining_mark;
+#[cfg(feature = "search_key")]
+use unicode_normalization::UnicodeNormalization;
+#[cfg(feature = "title_case")]
+use unicode_segmentation::UnicodeSegmentation;
+#[cfg(feature = "display_width")]
+use unicode_width::UnicodeWidthStr;
 
-use static_assertions::{assert_eq_align, assert_eq_size, assert_impl_all, assert_not_impl_any};
+use static_assertions::{
+    assert_eq_align, assert_eq_size, assert_impl_all, assert_not_impl_any, const_assert_eq,
+};
 
 use crate::storage::heap::HeapStr;
-use crate::storage::inline::InlineFlexStr;
+#[doc(inline)]
+pub use crate::storage::inline::InlineFlexStr;
 pub use crate::storage::inline::STRING_SIZED_INLINE;
 use crate::storage::static_ref::StaticStr;
-pub use crate::storage::{StorageType, WrongStorageType};
+pub use crate::storage::{CapacityError, StoragePolicy, StorageType, WrongStorageType};
 #[doc(inline)]
 pub use crate::traits::*;
+#[cfg(feature = "base64")]
+pub use base64::DecodeError as Base64DecodeError;
 
 // Trick to test README samples (from: https://github.com/rust-lang/cargo/issues/383#issuecomment-720873790)
 #[cfg(doctest)]
@@ -129,13 +167,22 @@ assert_eq_size!(StaticStr<PTR_SIZED_PAD>, InlineFlexStr<STRING_SIZED_INLINE>);
 assert_eq_align!(HeapStr<PTR_SIZED_PAD, Rc<str>>, InlineFlexStr<STRING_SIZED_INLINE>);
 assert_eq_align!(StaticStr<PTR_SIZED_PAD>, InlineFlexStr<STRING_SIZED_INLINE>);
 
+#[cfg(target_pointer_width = "64")]
+const_assert_eq!(STRING_SIZED_INLINE, 22);
+#[cfg(target_pointer_width = "64")]
+const_assert_eq!(PTR_SIZED_PAD, 7);
+#[cfg(target_pointer_width = "32")]
+const_assert_eq!(STRING_SIZED_INLINE, 10);
+#[cfg(target_pointer_width = "32")]
+const_assert_eq!(PTR_SIZED_PAD, 3);
+
 const BAD_SIZE_OR_ALIGNMENT: &str = "OOPS! It seems you are trying to create a custom `FlexStr` but have \
 violated the invariants on size and alignment. It is recommended to only try and use `FlexStrBase` \
 and pick a storage type with a size of exactly two machine words (16 bytes on 64-bit, 8 bytes on 32-bit). \
 Creating a custom type based directly on the `FlexStr` union is possible, but it is difficult to calculate \
 all the type parameters correctly and is therefore not recommended.";
 
-/// Padding the size of a pointer for this platform minus one
+/// Padding the size of a pointer for this platform minus one (7 bytes on 64-bit, 3 bytes on 32-bit)
 pub const PTR_SIZED_PAD: usize = mem::size_of::<*const ()>() - 1;
 
 /// A flexible string type that transparently wraps a string literal, inline string, or a heap allocated type
@@ -291,6 +338,31 @@ impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
         }
     }
 
+    /// Creates a wrapped static string literal, the same as [from_static](FlexStr::from_static),
+    /// but panics if the literal is too long to ever be inlined. When used to initialize a
+    /// constant, the check happens at compile time and a literal that is too long is a build
+    /// error. The string is still stored as a static string (no copy is made).
+    /// ```
+    /// use flexstr::LocalStr;
+    ///
+    /// const S: LocalStr = LocalStr::from_static_inline_checked("short");
+    /// assert!(S.is_static());
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use flexstr::LocalStr;
+    ///
+    /// const S: LocalStr = LocalStr::from_static_inline_checked("This is too long to be inlined!!!");
+    /// ```
+    #[inline]
+    pub const fn from_static_inline_checked(s: &'static str) -> Self {
+        assert!(
+            s.len() <= SIZE,
+            "the static string is too long to be inlined"
+        );
+        Self::from_static(s)
+    }
+
     /// Creates a new string from a [str] reference. If the string is empty, an empty static string
     /// is returned. If at or under the inline length limit, an inline string will be returned.
     /// Otherwise, a heap based string will be allocated and returned. This is typically used to
@@ -396,13 +468,251 @@ impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
         }
     }
 
+    /// Builds a new heap based string by passing a [String] with the given capacity to `f`. Unlike
+    /// other string creation functions, the result is never inlined, even if short enough. This is
+    /// intended for strings that will be cloned many times (for example, a [SharedStr] shared
+    /// across threads), trading a heap allocation for clones that only update a reference count.
+    /// ```
+    /// use core::fmt::Write;
+    /// use flexstr::SharedStr;
+    ///
+    /// let s = SharedStr::build_heap(16, |buffer| {
+    ///     write!(buffer, "worker-{}", 7).unwrap();
+    /// });
+    /// assert!(s.is_heap());
+    /// assert_eq!(s, "worker-7");
+    /// ```
+    pub fn build_heap(capacity: usize, f: impl FnOnce(&mut String)) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        let mut buffer = String::with_capacity(capacity);
+        f(&mut buffer);
+        Self::from_ref_heap(buffer)
+    }
+
+    /// Encodes `data` as standard (padded) base64. The encoded length is calculated up front, so
+    /// short results are encoded directly into an inline string and longer ones need only a single
+    /// allocation. Data of up to 15 bytes (on 64-bit) results in an inline string.
+    ///
+    /// Requires the `base64` feature.
+    /// ```
+    /// use flexstr::LocalStr;
+    ///
+    /// let s = LocalStr::encode_base64(b"flexstr");
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "ZmxleHN0cg==");
+    /// assert_eq!(s.decode_base64().unwrap(), b"flexstr");
+    /// ```
+    #[cfg(feature = "base64")]
+    pub fn encode_base64(data: &[u8]) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        let len = base64::encoded_len(data.len(), true).expect("base64 length overflow");
+
+        if len <= SIZE {
+            let mut buffer = [0; SIZE];
+            // SAFETY: The buffer was just verified to be large enough
+            let len = unsafe { STANDARD.encode_slice(data, &mut buffer).unwrap_unchecked() };
+            // SAFETY: Base64 output is always ASCII and the encoded length fits inline
+            unsafe {
+                let encoded = core::str::from_utf8_unchecked(&buffer[..len]);
+                Self::try_inline(encoded).unwrap_unchecked()
+            }
+        } else {
+            Self::from_ref(STANDARD.encode(data))
+        }
+    }
+
+    /// Creates a new string using exactly the requested kind of storage, which is useful for tests
+    /// and benchmarks that need to exercise a specific storage type regardless of length. Since
+    /// static storage requires a `'static` lifetime, the source must be a string literal (use
+    /// [try_inline](FlexStr::try_inline) or [from_ref_heap](FlexStr::from_ref_heap) directly for
+    /// other sources). If inline storage is requested but the string is too long, a
+    /// [CapacityError] is returned.
+    /// ```
+    /// use flexstr::{LocalStr, StorageType};
+    ///
+    /// let s = LocalStr::with_storage("short", StorageType::Heap).unwrap();
+    /// assert!(s.is_heap());
+    /// let s = LocalStr::with_storage("short", StorageType::Inline).unwrap();
+    /// assert!(s.is_inline());
+    /// let s = LocalStr::with_storage("short", StorageType::Static).unwrap();
+    /// assert!(s.is_static());
+    ///
+    /// let long = "This is too long to be inlined!!!!";
+    /// assert!(LocalStr::with_storage(long, StorageType::Inline).is_err());
+    /// ```
+    pub fn with_storage(s: &'static str, kind: StorageType) -> Result<Self, CapacityError>
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        match kind {
+            StorageType::Static => Ok(Self::from_static(s)),
+            StorageType::Inline => Self::try_inline(s).map_err(|s| CapacityError {
+                required: s.len(),
+                capacity: SIZE,
+            }),
+            StorageType::Heap => Ok(Self::from_ref_heap(s)),
+        }
+    }
+
+    /// Concatenates the strings from `iter` into a new string. Unlike [collect](Iterator::collect),
+    /// the iterator is first cloned to sum the lengths of the strings, so that the exact capacity
+    /// is known up front and any heap buffer is allocated only once. This should only be used
+    /// with iterators that are cheap to clone (such as [slice::iter]).
+    /// ```
+    /// use flexstr::LocalStr;
+    ///
+    /// let parts = ["This ", "is ", "too ", "long ", "to ", "be ", "inlined"];
+    /// let s = LocalStr::concat_from_iter(parts.iter());
+    /// assert!(s.is_heap());
+    /// assert_eq!(s, "This is too long to be inlined");
+    /// ```
+    pub fn concat_from_iter<I, S>(iter: I) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+        I: IntoIterator<Item = S>,
+        I::IntoIter: Clone,
+        S: AsRef<str>,
+    {
+        let iter = iter.into_iter();
+        let cap = iter.clone().map(|s| s.as_ref().len()).sum();
+
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, cap);
+        for s in iter {
+            builder.str_write(s);
+        }
+        builder_into!(builder, buffer)
+    }
+
+    /// Creates a new string from an iterator of decoded chars, substituting
+    /// [REPLACEMENT_CHARACTER](char::REPLACEMENT_CHARACTER) (U+FFFD) for each decode error instead of
+    /// stopping at the first one (as collecting into a `Result` would).
+    /// ```
+    /// use flexstr::LocalStr;
+    ///
+    /// let s = LocalStr::from_chars_lossy(char::decode_utf16([0x0068, 0xD800, 0x0069]));
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "h\u{FFFD}i");
+    /// ```
+    #[inline]
+    pub fn from_chars_lossy<E>(iter: impl IntoIterator<Item = Result<char, E>>) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        from_iter_char(iter, |ch| ch.unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+
     #[inline]
     fn from_char(ch: char) -> Self {
         // SAFETY: Regardless of architecture, 4 bytes will always fit in an inline string
         unsafe { Self::try_inline(ch.encode_utf8(&mut [0; 4])).unwrap_unchecked() }
     }
 
-    /// Returns the size of the maximum possible inline length for this type
+    /// Reads all bytes from `r` until EOF into a new string, with the same semantics as
+    /// [read_to_string](std::io::Read::read_to_string). The contents are validated as UTF-8 while
+    /// reading without an intermediate [String], so short contents will be inlined. If the data is
+    /// not valid UTF-8, an error of kind [InvalidData](std::io::ErrorKind::InvalidData) is returned.
+    /// ```
+    /// use std::io::Read;
+    /// use flexstr::LocalStr;
+    ///
+    /// let s = LocalStr::from_reader("key = ☃".as_bytes()).unwrap();
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "key = ☃");
+    ///
+    /// // A char split across reads is still decoded
+    /// let snowman = "☃".as_bytes();
+    /// let s = LocalStr::from_reader(snowman[..1].chain(&snowman[1..])).unwrap();
+    /// assert_eq!(s, "☃");
+    ///
+    /// assert!(LocalStr::from_reader(&snowman[..2]).is_err());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_reader(mut r: impl io::Read) -> io::Result<Self>
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer);
+        let mut chunk = [0; 512];
+        // Number of bytes at the start of `chunk` holding an incomplete char from the last read
+        let mut pending = 0;
+
+        loop {
+            let filled = match r.read(&mut chunk[pending..]) {
+                Ok(0) => break,
+                Ok(read) => pending + read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+
+            let valid = match core::str::from_utf8(&chunk[..filled]) {
+                Ok(s) => s.len(),
+                // Only an incomplete char at the very end is acceptable (the rest may follow)
+                Err(err) if err.error_len().is_none() => err.valid_up_to(),
+                Err(_) => return Err(invalid_utf8()),
+            };
+
+            // SAFETY: We just validated these bytes are UTF-8
+            builder.str_write(unsafe { core::str::from_utf8_unchecked(&chunk[..valid]) });
+            chunk.copy_within(valid..filled, 0);
+            pending = filled - valid;
+        }
+
+        if pending > 0 {
+            Err(invalid_utf8())
+        } else {
+            Ok(builder_into!(builder, buffer))
+        }
+    }
+
+    /// Creates an inline string consisting of `n` copies of `ch`. Unlike [repeat_n](Repeat::repeat_n),
+    /// this will never fall back to heap allocation - if the result would not fit in the inline
+    /// buffer a [CapacityError] is returned instead. Like the other constructors, an empty result is
+    /// the static empty string rather than an inline one.
+    /// ```
+    /// use flexstr::LocalStr;
+    ///
+    /// let s = LocalStr::inline_fill('☃', 4).unwrap();
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "☃☃☃☃");
+    ///
+    /// let empty = LocalStr::inline_fill('☃', 0).unwrap();
+    /// assert!(empty.is_static());
+    /// assert_eq!(empty, "");
+    ///
+    /// let err = LocalStr::inline_fill('☃', 100).unwrap_err();
+    /// assert_eq!(err.required, 300);
+    /// ```
+    #[inline]
+    pub fn inline_fill(ch: char, n: usize) -> Result<Self, CapacityError> {
+        let mut buffer = [0; 4];
+        let ch = ch.encode_utf8(&mut buffer);
+
+        match ch.len().checked_mul(n) {
+            Some(0) => Ok(Self::EMPTY),
+            Some(len) if len <= SIZE => {
+                // SAFETY: An empty string will always fit in an inline string
+                let mut s = unsafe { InlineFlexStr::try_new("").unwrap_unchecked() };
+                for _ in 0..n {
+                    // We checked the total length above, so this always succeeds
+                    s.try_concat(ch);
+                }
+                Ok(Self::from_inline(s))
+            }
+            len => Err(CapacityError {
+                required: len.unwrap_or(usize::MAX),
+                capacity: SIZE,
+            }),
+        }
+    }
+
+    /// Returns the size of the maximum possible inline length for this type (for [LocalStr] and
+    /// [SharedStr] this is 22 bytes on 64-bit and 10 bytes on 32-bit platforms)
     /// ```
     /// use flexstr::{LocalStr, STRING_SIZED_INLINE};
     ///
@@ -414,16 +724,24 @@ impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
     }
 
     /// Attempts to extract a static inline string literal if one is stored inside this [LocalStr].
-    /// Returns [WrongStorageType] if this is not a static string literal.
+    /// Returns [WrongStorageType] if this is not a static string literal. This is `const fn` so it
+    /// can be used to extract the literal from a constant at compile time.
     /// ```
-    /// use flexstr::local_str;
+    /// use flexstr::{local_str, LocalStr};
     ///
     /// let s = "abc";
     /// let s2 = local_str!(s);
     /// assert_eq!(s2.try_as_static_str().unwrap(), s);
+    ///
+    /// const S: &LocalStr = &local_str!("abc");
+    /// const LIT: &str = match S.try_as_static_str() {
+    ///     Ok(lit) => lit,
+    ///     Err(_) => panic!("not a static string"),
+    /// };
+    /// assert_eq!(LIT, s);
     /// ```
     #[inline]
-    pub fn try_as_static_str(&self) -> Result<&'static str, WrongStorageType> {
+    pub const fn try_as_static_str(&self) -> Result<&'static str, WrongStorageType> {
         // SAFETY: Marker check is aligned to correct accessed field
         unsafe {
             match self.static_str.marker {
@@ -436,6 +754,26 @@ impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
         }
     }
 
+    /// Extracts the static string literal stored inside this [FlexStr] without checking the storage
+    /// type. This is `const fn` so it can be used to build compile time tables from constants
+    /// created via [from_static](FlexStr::from_static).
+    ///
+    /// # Safety
+    /// The caller must ensure this string uses static storage (see [is_static](FlexStr::is_static)).
+    /// Calling this on an inline or heap string is undefined behavior.
+    /// ```
+    /// use flexstr::{local_str, LocalStr};
+    ///
+    /// const S: &LocalStr = &local_str!("abc");
+    /// const LEN: usize = unsafe { S.as_static_str_unchecked() }.len();
+    /// assert_eq!(LEN, 3);
+    /// ```
+    #[inline]
+    pub const unsafe fn as_static_str_unchecked(&self) -> &'static str {
+        // SAFETY: Caller guarantees this is a static string
+        self.static_str.literal
+    }
+
     /// Attempts to extract a copy of the heap value (for [LocalStr] this will be an [`Rc<str>`] and
     /// for [SharedStr] an [`Arc<str>`]) via cloning. If this is not a heap based string, a
     /// [WrongStorageType] error will be returned.
@@ -492,7 +830,7 @@ impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
     /// assert!(s.is_static());
     /// ```
     #[inline]
-    pub fn is_static(&self) -> bool {
+    pub const fn is_static(&self) -> bool {
         // SAFETY: Marker is identical in all union fields
         unsafe { matches!(self.static_str.marker, StorageType::Static) }
     }
@@ -505,7 +843,7 @@ impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
     /// assert!(s.is_inline());
     /// ```
     #[inline]
-    pub fn is_inline(&self) -> bool {
+    pub const fn is_inline(&self) -> bool {
         // SAFETY: Marker is identical in all union fields
         unsafe { matches!(self.static_str.marker, StorageType::Inline) }
     }
@@ -518,7 +856,7 @@ impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
     /// assert!(s.is_heap());
     /// ```
     #[inline]
-    pub fn is_heap(&self) -> bool {
+    pub const fn is_heap(&self) -> bool {
         // SAFETY: Marker is identical in all union fields
         unsafe { matches!(self.static_str.marker, StorageType::Heap) }
     }
@@ -587,107 +925,3291 @@ where
         String::from(&**self)
     }
 
+    /// Converts this [FlexStr] into its bytes, like [String::into_bytes]. The contents are always
+    /// copied into a new exactly sized [Vec], regardless of storage: static and inline strings
+    /// have no allocation to give up, and the allocation of an `Rc<str>`/`Arc<str>` also holds the
+    /// reference counts, so it cannot be reused as a [Vec] even when uniquely owned.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let bytes = local_str!("abc").into_bytes();
+    /// assert_eq!(bytes, b"abc");
+    /// ```
     #[inline]
-    fn add(mut self, rhs: &str) -> Self
-    where
-        HEAP: for<'a> From<&'a str>,
-    {
-        if rhs.is_empty() {
-            self
-        } else if self.is_empty() {
-            rhs.into()
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    /// Returns the number of times `ch` occurs in this string. For ASCII chars this performs a
+    /// simple byte scan without any UTF-8 decoding.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("a,b,c,☃,☃");
+    /// assert_eq!(s.count(','), 4);
+    /// assert_eq!(s.count('☃'), 2);
+    /// assert_eq!(s.count('x'), 0);
+    /// ```
+    #[inline]
+    pub fn count(&self, ch: char) -> usize {
+        if ch.is_ascii() {
+            let b = ch as u8;
+            // A simple filter/count loop like this is easily vectorized by the compiler
+            self.as_bytes().iter().filter(|&&b2| b2 == b).count()
         } else {
-            // SAFETY: Marker check is aligned to correct accessed field
-            unsafe {
-                match self.static_str.marker {
-                    StorageType::Static => concat(self.static_str.literal, rhs),
-                    StorageType::Inline => {
-                        let s = &mut self.inline_str;
+            self.matches(ch).count()
+        }
+    }
 
-                        if s.try_concat(rhs) {
-                            self
-                        } else {
-                            concat(s, rhs)
-                        }
-                    }
-                    StorageType::Heap => concat(&self.heap_str.heap, rhs),
-                }
-            }
+    /// Returns the number of non-overlapping occurrences of `pat` in this string. As with
+    /// [str::matches], an empty pattern matches at every char boundary.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("abababa");
+    /// assert_eq!(s.count_str("aba"), 2);
+    /// assert_eq!(s.count_str("c"), 0);
+    /// ```
+    #[inline]
+    pub fn count_str(&self, pat: &str) -> usize {
+        match pat.len() {
+            1 => self.count(pat.as_bytes()[0] as char),
+            _ => self.matches(pat).count(),
         }
     }
-}
 
-#[inline]
-fn concat<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>(
-    s1: &str,
-    s2: &str,
-) -> FlexStr<SIZE, PAD1, PAD2, HEAP>
-where
-    HEAP: for<'a> From<&'a str>,
-{
-    let mut buffer = buffer_new!(SIZE);
-    let mut builder = builder_new!(buffer, s1.len() + s2.len());
-    builder.str_write(s1);
-    builder.str_write(s2);
-    builder_into!(builder, buffer)
-}
+    /// Returns the number of columns this string occupies when displayed in a terminal (as
+    /// determined by the `unicode-width` crate). East Asian wide chars occupy two columns and
+    /// combining marks none, so this is the correct value for aligning terminal output rather than
+    /// [len](FlexStr::len) or the number of chars. ASCII strings simply return their length.
+    ///
+    /// Requires the `display_width` feature.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert_eq!(local_str!("table").display_width(), 5);
+    /// assert_eq!(local_str!("表格").display_width(), 4);
+    /// assert_eq!(local_str!("e\u{301}").display_width(), 1);
+    /// ```
+    #[cfg(feature = "display_width")]
+    #[inline]
+    pub fn display_width(&self) -> usize {
+        if self.is_ascii() {
+            self.len()
+        } else {
+            UnicodeWidthStr::width(self.as_str())
+        }
+    }
 
-#[inline]
-fn from_iter_str<const SIZE: usize, const PAD1: usize, const PAD2: usize, I, HEAP, U>(
-    iter: I,
-) -> FlexStr<SIZE, PAD1, PAD2, HEAP>
-where
-    I: IntoIterator<Item = U>,
-    HEAP: for<'b> From<&'b str>,
-    U: AsRef<str>,
-{
-    let iter = iter.into_iter();
+    /// Returns true if this string starts with any of the strings in `pats`.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let url = local_str!("https://example.com");
+    /// assert!(url.starts_with_any(&["http://", "https://"]));
+    /// assert!(!url.starts_with_any(&["ftp://"]));
+    /// ```
+    #[inline]
+    pub fn starts_with_any(&self, pats: &[&str]) -> bool {
+        pats.iter().any(|pat| self.starts_with(pat))
+    }
 
-    // Since `IntoIterator` consumes, we cannot loop over it twice to find lengths of strings
-    // for a good capacity # without cloning it (which might be expensive)
-    let mut buffer = buffer_new!(SIZE);
-    let mut builder = builder_new!(buffer);
-    for s in iter {
-        builder.str_write(s);
+    /// Returns true if this string ends with any of the strings in `pats`.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let file = local_str!("photo.png");
+    /// assert!(file.ends_with_any(&[".jpg", ".png", ".gif"]));
+    /// assert!(!file.ends_with_any(&[".PNG"]));
+    /// ```
+    #[inline]
+    pub fn ends_with_any(&self, pats: &[&str]) -> bool {
+        pats.iter().any(|pat| self.ends_with(pat))
     }
-    builder_into!(builder, buffer)
-}
 
-#[inline]
-fn from_iter_char<const SIZE: usize, const PAD1: usize, const PAD2: usize, I, F, HEAP, U>(
-    iter: I,
-    f: F,
-) -> FlexStr<SIZE, PAD1, PAD2, HEAP>
-where
-    I: IntoIterator<Item = U>,
-    F: Fn(U) -> char,
-    HEAP: for<'b> From<&'b str>,
-{
-    let iter = iter.into_iter();
-    let (lower, _) = iter.size_hint();
+    /// Returns true if this string ends with any of the strings in `pats`, ignoring ASCII case
+    /// (for example, when checking file extensions). No allocation is performed.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert!(local_str!("PHOTO.PNG").ends_with_any_ignore_case(&[".jpg", ".png"]));
+    /// assert!(!local_str!("photo.png.txt").ends_with_any_ignore_case(&[".jpg", ".png"]));
+    /// ```
+    #[inline]
+    pub fn ends_with_any_ignore_case(&self, pats: &[&str]) -> bool {
+        let bytes = self.as_bytes();
 
-    let mut buffer = buffer_new!(SIZE);
-    let mut builder = builder_new!(buffer, lower);
-    for ch in iter {
-        builder.char_write(f(ch));
+        pats.iter().any(|pat| {
+            bytes.len() >= pat.len()
+                && bytes[bytes.len() - pat.len()..].eq_ignore_ascii_case(pat.as_bytes())
+        })
     }
-    builder_into!(builder, buffer)
-}
 
-/// Equivalent to the [format](std::fmt::format) function from stdlib. Efficiently creates a native [FlexStr]
-pub fn flex_fmt<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>(
-    args: Arguments<'_>,
-) -> FlexStr<SIZE, PAD1, PAD2, HEAP>
-where
-    HEAP: for<'a> From<&'a str>,
-{
-    // NOTE: We have a disadvantage to [String] because we cannot call `estimated_capacity()` on args
-    // As such, we cannot assume a given needed capacity - we start with a stack allocated buffer
-    // and only promote to a heap buffer if a write won't fit
-    let mut buffer = buffer_new!(SIZE);
-    let mut builder = builder_new!(buffer);
-    builder
-        .write_fmt(args)
-        .expect("a formatting trait implementation returned an error");
-    builder_into!(builder, buffer)
+    /// Returns true if this string contains no ASCII uppercase letters (all other chars, including
+    /// non-ASCII letters, are ignored). The scan stops at the first uppercase letter.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert!(local_str!("content-type: ☃").is_ascii_lowercase());
+    /// assert!(!local_str!("Content-Type").is_ascii_lowercase());
+    /// ```
+    #[inline]
+    pub fn is_ascii_lowercase(&self) -> bool {
+        !self.bytes().any(|b| b.is_ascii_uppercase())
+    }
+
+    /// Returns true if this string contains no ASCII lowercase letters (all other chars, including
+    /// non-ASCII letters, are ignored). The scan stops at the first lowercase letter.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert!(local_str!("GET /INDEX").is_ascii_uppercase());
+    /// assert!(!local_str!("Get").is_ascii_uppercase());
+    /// ```
+    #[inline]
+    pub fn is_ascii_uppercase(&self) -> bool {
+        !self.bytes().any(|b| b.is_ascii_lowercase())
+    }
+
+    /// Compares this string to `other` as if both were ASCII lowercased, without allocating. Strings
+    /// that differ only in ASCII case compare as [Ordering::Equal] (ties are not broken by the
+    /// original bytes), which is consistent with [eq_ignore_ascii_case](str::eq_ignore_ascii_case).
+    /// See [CaseInsensitiveOrd] to sort or store strings using this ordering.
+    /// ```
+    /// use core::cmp::Ordering;
+    /// use flexstr::local_str;
+    ///
+    /// assert_eq!(local_str!("Content-Type").cmp_ignore_ascii_case("content-type"), Ordering::Equal);
+    /// assert_eq!(local_str!("accept").cmp_ignore_ascii_case("Host"), Ordering::Less);
+    /// ```
+    #[inline]
+    pub fn cmp_ignore_ascii_case(&self, other: &str) -> Ordering {
+        cmp_ignore_ascii_case(self, other)
+    }
+
+    /// Returns the first char of this string, or `None` if it is empty. Only the first char is
+    /// decoded.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert_eq!(local_str!("/usr/bin").first_char(), Some('/'));
+    /// assert_eq!(local_str!("").first_char(), None);
+    /// ```
+    #[inline]
+    pub fn first_char(&self) -> Option<char> {
+        self.chars().next()
+    }
+
+    /// Returns the last char of this string, or `None` if it is empty. The char is decoded
+    /// backwards from the end of the string, so this does not iterate the whole string.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert_eq!(local_str!("/usr/bin/").last_char(), Some('/'));
+    /// assert_eq!(local_str!("snowman ☃").last_char(), Some('☃'));
+    /// assert_eq!(local_str!("").last_char(), None);
+    /// ```
+    #[inline]
+    pub fn last_char(&self) -> Option<char> {
+        self.chars().next_back()
+    }
+
+    /// Returns an iterator over the chars of this string and their byte offsets, like
+    /// [char_indices](str::char_indices). ASCII bytes are yielded directly without any UTF-8
+    /// decoding, which is faster for mostly ASCII text.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("a☃b");
+    /// let entries: Vec<_> = s.char_entries().collect();
+    /// assert_eq!(entries, [(0, 'a'), (1, '☃'), (4, 'b')]);
+    /// assert!(s.char_entries().eq(s.char_indices()));
+    /// ```
+    #[inline]
+    pub fn char_entries(&self) -> impl Iterator<Item = (usize, char)> + '_ {
+        let mut idx = 0;
+
+        iter::from_fn(move || {
+            let b = *self.as_bytes().get(idx)?;
+            let ch = if b.is_ascii() {
+                b as char
+            } else {
+                // SAFETY: `idx` is always on a char boundary and is not at the end of the string
+                unsafe { self.get_unchecked(idx..).chars().next().unwrap_unchecked() }
+            };
+
+            let entry = (idx, ch);
+            idx += ch.len_utf8();
+            Some(entry)
+        })
+    }
+
+    /// Like [char_entries](FlexStr::char_entries), but each entry also includes the char as a
+    /// new (always inline) string.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let (idx, ch, s) = local_str!("a☃b").char_entries_flex().nth(1).unwrap();
+    /// assert_eq!((idx, ch), (1, '☃'));
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "☃");
+    /// ```
+    #[inline]
+    pub fn char_entries_flex(&self) -> impl Iterator<Item = (usize, char, Self)> + '_ {
+        self.char_entries()
+            .map(|(idx, ch)| (idx, ch, Self::from_char(ch)))
+    }
+
+    /// Appends `ch` to this string in place if the result fits in inline storage, otherwise
+    /// returns `Err(ch)` and leaves this string unchanged. On success this string is always inline
+    /// (a static or heap based string short enough is first copied into inline storage), so unlike
+    /// `+` this never promotes the string to the heap.
+    /// ```
+    /// use flexstr::LocalStr;
+    ///
+    /// let mut code = LocalStr::from_static("AB");
+    /// assert_eq!(code.try_push_char('☃'), Ok(()));
+    /// assert!(code.is_inline());
+    /// assert_eq!(code, "AB☃");
+    ///
+    /// let mut full = LocalStr::from_ref("x".repeat(22));
+    /// assert_eq!(full.try_push_char('!'), Err('!'));
+    /// assert_eq!(full.len(), 22);
+    /// ```
+    pub fn try_push_char(&mut self, ch: char) -> Result<(), char> {
+        let mut buffer = [0; 4];
+        let ch_str = ch.encode_utf8(&mut buffer);
+
+        if self.len() + ch_str.len() > SIZE {
+            return Err(ch);
+        }
+
+        if !self.is_inline() {
+            // SAFETY: We just verified the length will fit inline
+            *self = unsafe { Self::try_inline(self.as_str()).unwrap_unchecked() };
+        }
+
+        // SAFETY: This is now always inline and we verified the char will fit
+        unsafe { self.inline_str.try_concat(ch_str) };
+        Ok(())
+    }
+
+    /// Returns this string without its last char along with that char, or `None` if it is empty.
+    /// The last char is decoded backwards from the end of the string. As with
+    /// [split_at_flex](FlexStr::split_at_flex), a static source will result in a static string.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let (s, ch) = local_str!("stack☃").pop_char().unwrap();
+    /// assert!(s.is_static());
+    /// assert_eq!(s, "stack");
+    /// assert_eq!(ch, '☃');
+    /// assert!(local_str!("").pop_char().is_none());
+    /// ```
+    #[inline]
+    pub fn pop_char(&self) -> Option<(Self, char)>
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        let ch = self.last_char()?;
+        let rest = &self[..self.len() - ch.len_utf8()];
+        Some((self.substr_of(rest), ch))
+    }
+
+    /// Converts a range of char indices into the equivalent range of byte indices, suitable for
+    /// slicing this string. Returns `None` if the start of the range is past the end, or if either
+    /// end is past the number of chars in the string. The end of the range may equal the number
+    /// of chars, which maps to the length of the string in bytes.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("a☃b☃c");
+    /// let range = s.char_range_to_byte_range(1..4).unwrap();
+    /// assert_eq!(range, 1..8);
+    /// assert_eq!(&s[range], "☃b☃");
+    /// assert_eq!(s.char_range_to_byte_range(5..5), Some(9..9));
+    /// assert_eq!(s.char_range_to_byte_range(2..6), None);
+    /// ```
+    pub fn char_range_to_byte_range(&self, chars: Range<usize>) -> Option<Range<usize>> {
+        if chars.start > chars.end {
+            return None;
+        }
+
+        // Byte offset of each char boundary (including the end of the string) by char index
+        let mut boundaries = self
+            .char_indices()
+            .map(|(idx, _)| idx)
+            .chain(iter::once(self.len()));
+
+        let start = boundaries.nth(chars.start)?;
+        let end = match chars.end - chars.start {
+            0 => start,
+            len => boundaries.nth(len - 1)?,
+        };
+        Some(start..end)
+    }
+
+    /// Returns a new string containing the chars of this string in the given range of char
+    /// (not byte) indices, or `None` if the range is out of bounds (as with
+    /// [char_range_to_byte_range](FlexStr::char_range_to_byte_range)). As with
+    /// [split_at_flex](FlexStr::split_at_flex), a static source will result in a static string.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("a☃b☃c");
+    /// let slice = s.char_slice(1..4).unwrap();
+    /// assert_eq!(slice, "☃b☃");
+    /// assert!(slice.is_static());
+    /// assert_eq!(s.char_slice(2..6), None);
+    /// ```
+    #[inline]
+    pub fn char_slice(&self, chars: Range<usize>) -> Option<Self>
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        let range = self.char_range_to_byte_range(chars)?;
+        Some(self.substr_of(&self[range]))
+    }
+
+    /// Returns the byte offset of this string within `parent` if this string shares its storage
+    /// with `parent` (points into the same memory), otherwise `None`. This only succeeds for:
+    /// * Static sub-slices: substrings of a static string (for example, from
+    ///   [split_at_flex](FlexStr::split_at_flex)) point into the same literal
+    /// * Heap clones: a clone of a heap based `parent` shares its heap value (at offset 0)
+    /// * The same value: any string compared with itself (at offset 0)
+    ///
+    /// Heap storage is exact sized and never shared by substrings, and inline strings store their
+    /// bytes inside each value, so substrings of heap strings return `None`, and an inline string
+    /// only matches itself (even a clone of `parent` returns `None`).
+    /// ```
+    /// use flexstr::{local_str, LocalStr};
+    ///
+    /// let src = local_str!("let x = 42;");
+    /// let (_, rest) = src.split_at_flex(8);
+    /// assert_eq!(rest.offset_in(&src), Some(8));
+    ///
+    /// let heap = LocalStr::from_ref_heap("let x = 42;");
+    /// let (_, rest) = heap.split_at_flex(8);
+    /// assert_eq!(rest.offset_in(&heap), None);
+    /// assert_eq!(heap.clone().offset_in(&heap), Some(0));
+    ///
+    /// let inline = LocalStr::from_ref("let x = 42;");
+    /// assert!(inline.is_inline());
+    /// assert_eq!(inline.clone().offset_in(&inline), None);
+    /// assert_eq!(inline.offset_in(&inline), Some(0));
+    /// ```
+    #[inline]
+    pub fn offset_in(&self, parent: &Self) -> Option<usize> {
+        let start = self.as_ptr() as usize;
+        let parent_start = parent.as_ptr() as usize;
+
+        if start >= parent_start && start + self.len() <= parent_start + parent.len() {
+            Some(start - parent_start)
+        } else {
+            None
+        }
+    }
+
+    /// Decodes this string as standard (padded) base64, returning an error if it is not valid.
+    ///
+    /// Requires the `base64` feature.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert_eq!(local_str!("ZmxleHN0cg==").decode_base64().unwrap(), b"flexstr");
+    /// assert!(local_str!("not base64!").decode_base64().is_err());
+    /// ```
+    #[cfg(feature = "base64")]
+    #[inline]
+    pub fn decode_base64(&self) -> Result<Vec<u8>, Base64DecodeError> {
+        STANDARD.decode(self.as_bytes())
+    }
+
+    /// Returns the byte index of the first occurrence of the byte `b` in this string, or `None` if
+    /// it does not occur. The index is a byte offset that will not be on a `char` boundary if `b`
+    /// is not ASCII, and it is the caller's responsibility to handle that.
+    ///
+    /// When the `memchr` feature is enabled, the search is vectorized.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("name,value,other");
+    /// assert_eq!(s.find_byte(b','), Some(4));
+    /// assert_eq!(s.find_byte(b'\n'), None);
+    /// ```
+    #[inline]
+    pub fn find_byte(&self, b: u8) -> Option<usize> {
+        #[cfg(feature = "memchr")]
+        {
+            memchr::memchr(b, self.as_bytes())
+        }
+        #[cfg(not(feature = "memchr"))]
+        {
+            self.as_bytes().iter().position(|&ch| ch == b)
+        }
+    }
+
+    /// Returns the byte index of the last occurrence of the byte `b` in this string, or `None` if
+    /// it does not occur. The index is a byte offset that will not be on a `char` boundary if `b`
+    /// is not ASCII, and it is the caller's responsibility to handle that.
+    ///
+    /// When the `memchr` feature is enabled, the search is vectorized.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("name,value,other");
+    /// assert_eq!(s.rfind_byte(b','), Some(10));
+    /// assert_eq!(s.rfind_byte(b'\n'), None);
+    /// ```
+    #[inline]
+    pub fn rfind_byte(&self, b: u8) -> Option<usize> {
+        #[cfg(feature = "memchr")]
+        {
+            memchr::memrchr(b, self.as_bytes())
+        }
+        #[cfg(not(feature = "memchr"))]
+        {
+            self.as_bytes().iter().rposition(|&ch| ch == b)
+        }
+    }
+
+    /// Returns the number of lines in this string, with the same result as
+    /// [lines](str::lines)`().count()` (a trailing line ending does not start an empty line), but
+    /// without iterating any lines. This is a single scan counting the `\n` bytes.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert_eq!(local_str!("one\ntwo\r\nthree").line_count(), 3);
+    /// assert_eq!(local_str!("one\ntwo\n").line_count(), 2);
+    /// assert_eq!(local_str!("").line_count(), 0);
+    /// ```
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        let newlines = self.count('\n');
+
+        match self.as_bytes().last() {
+            Some(b'\n') | None => newlines,
+            Some(_) => newlines + 1,
+        }
+    }
+
+    /// Returns the number of `u16` code units this string would occupy if encoded as UTF-16. This
+    /// is useful to size buffers before encoding without actually encoding.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert_eq!(local_str!("ascii").utf16_len(), 5);
+    /// assert_eq!(local_str!("☃").utf16_len(), 1);
+    /// assert_eq!(local_str!("a😀b").utf16_len(), "a😀b".encode_utf16().count());
+    /// ```
+    #[inline]
+    pub fn utf16_len(&self) -> usize {
+        if self.is_ascii() {
+            self.len()
+        } else {
+            // Every char is one code unit except 4 byte UTF-8 sequences which need a surrogate pair.
+            // Counting lead bytes avoids having to fully decode each char
+            self.as_bytes()
+                .iter()
+                .map(|&b| match b {
+                    0x80..=0xBF => 0,
+                    0xF0..=0xFF => 2,
+                    _ => 1,
+                })
+                .sum()
+        }
+    }
+
+    /// Returns a [Path] view of this [FlexStr] without allocating
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("/tmp/file.txt");
+    /// assert_eq!(s.as_path().extension().unwrap(), "txt");
+    /// ```
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn as_path(&self) -> &Path {
+        Path::new(self.as_str())
+    }
+
+    /// Returns an [OsStr] view of this [FlexStr] without allocating
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("file.txt");
+    /// assert_eq!(s.as_os_str(), "file.txt");
+    /// ```
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn as_os_str(&self) -> &OsStr {
+        OsStr::new(self.as_str())
+    }
+
+    /// Returns true if every char in this [FlexStr] satisfies the predicate. An empty string
+    /// always returns true.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("ident_123");
+    /// assert!(s.all_chars(|ch| ch.is_alphanumeric() || ch == '_'));
+    /// assert!(!s.all_chars(char::is_alphabetic));
+    /// ```
+    #[inline]
+    pub fn all_chars(&self, pred: impl FnMut(char) -> bool) -> bool {
+        self.chars().all(pred)
+    }
+
+    /// Returns true if any char in this [FlexStr] satisfies the predicate. An empty string
+    /// always returns false.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("snowman: ☃");
+    /// assert!(s.any_char(|ch| !ch.is_ascii()));
+    /// assert!(!s.any_char(char::is_numeric));
+    /// ```
+    #[inline]
+    pub fn any_char(&self, pred: impl FnMut(char) -> bool) -> bool {
+        self.chars().any(pred)
+    }
+
+    /// ASCII-only fast path for [all_chars](FlexStr::all_chars). Scans the raw bytes without any
+    /// UTF-8 decoding and returns true only if every byte is ASCII and satisfies the predicate.
+    /// Any non-ASCII char causes this to return false.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("ident_123");
+    /// assert!(s.all_ascii(|b| b.is_ascii_alphanumeric() || b == b'_'));
+    ///
+    /// let s = local_str!("idént");
+    /// assert!(!s.all_ascii(|b| b.is_ascii_alphanumeric()));
+    /// ```
+    #[inline]
+    pub fn all_ascii(&self, mut pred: impl FnMut(u8) -> bool) -> bool {
+        self.as_bytes().iter().all(|&b| b.is_ascii() && pred(b))
+    }
+
+    /// ASCII-only fast path for [any_char](FlexStr::any_char). Scans the raw bytes without any
+    /// UTF-8 decoding and returns true if any ASCII byte satisfies the predicate. Bytes that are
+    /// part of a multibyte char are never passed to the predicate.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("snowman ☃ 1");
+    /// assert!(s.any_ascii(|b| b.is_ascii_digit()));
+    /// assert!(!s.any_ascii(|b| b.is_ascii_uppercase()));
+    /// ```
+    #[inline]
+    pub fn any_ascii(&self, mut pred: impl FnMut(u8) -> bool) -> bool {
+        // All bytes of a multibyte UTF-8 sequence are >= 0x80, so they can never be mistaken for ASCII
+        self.as_bytes().iter().any(|&b| b.is_ascii() && pred(b))
+    }
+
+    /// Returns true if this string contains any control chars (such as escape sequences or other
+    /// non-printing chars, including `\n` and `\t`). ASCII strings are checked byte by byte
+    /// without any UTF-8 decoding.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert!(local_str!("red \x1b[31m").has_control_chars());
+    /// assert!(local_str!("snowman \u{9b}").has_control_chars());
+    /// assert!(!local_str!("snowman ☃").has_control_chars());
+    /// ```
+    #[inline]
+    pub fn has_control_chars(&self) -> bool {
+        if self.is_ascii() {
+            self.bytes().any(|b| b.is_ascii_control())
+        } else {
+            self.chars().any(char::is_control)
+        }
+    }
+
+    /// Returns true if this string contains no control chars and is therefore safe to print to a
+    /// terminal. If `allow_whitespace` is true, whitespace control chars (such as `\n` and `\t`)
+    /// are permitted. ASCII strings are checked byte by byte without any UTF-8 decoding.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("line 1\n\tline 2");
+    /// assert!(s.is_printable(true));
+    /// assert!(!s.is_printable(false));
+    /// assert!(!local_str!("bell\x07").is_printable(true));
+    /// ```
+    #[inline]
+    pub fn is_printable(&self, allow_whitespace: bool) -> bool {
+        if self.is_ascii() {
+            !self.bytes().any(|b| {
+                b.is_ascii_control() && !(allow_whitespace && char::from(b).is_whitespace())
+            })
+        } else {
+            !self
+                .chars()
+                .any(|ch| ch.is_control() && !(allow_whitespace && ch.is_whitespace()))
+        }
+    }
+
+    /// Splits this [FlexStr] into two at the given byte index, returning both halves as new
+    /// [FlexStr] values. Panics if `mid` is not on a char boundary or is past the end of the string.
+    ///
+    /// The storage of each half depends on the source:
+    /// * Static: both halves are static sub-slices of the original literal (no copying)
+    /// * Inline: both halves are inlined (they are always short enough)
+    /// * Heap: each half is inlined if it fits, otherwise it is heap allocated
+    /// ```
+    /// use flexstr::{local_str, LocalStr};
+    ///
+    /// let (a, b) = local_str!("This literal is far too long to be inlined").split_at_flex(4);
+    /// assert!(a.is_static() && b.is_static());
+    /// assert_eq!(a, "This");
+    /// assert_eq!(b, " literal is far too long to be inlined");
+    ///
+    /// let (a, b) = LocalStr::from_ref_heap("heap string").split_at_flex(4);
+    /// assert!(a.is_inline() && b.is_inline());
+    /// assert_eq!(a, "heap");
+    /// assert_eq!(b, " string");
+    /// ```
+    #[inline]
+    pub fn split_at_flex(&self, mid: usize) -> (Self, Self)
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        let (first, second) = self.split_at(mid);
+        (self.substr_of(first), self.substr_of(second))
+    }
+
+    /// Capitalizes the first letter of each sentence. The first letter of the string is always
+    /// capitalized, as is the first letter following a sentence terminator (`.`, `!` or `?`) and
+    /// at least one whitespace char. Closing quotes and brackets directly after a terminator (as
+    /// in `."` or `!)`) are part of the sentence end, and punctuation before the next letter (such
+    /// as an opening quote) is skipped over. A sentence starting with a digit is left unchanged.
+    /// Capitalizing uses title case, so a letter such as `ß` or `ﬁ` becomes `Ss` or `Fi`.
+    /// If no chars need to change, a clone of this string is returned instead of building a new
+    /// one.
+    ///
+    /// # Limitations
+    /// Abbreviations are not detected, so a string like "e.g. this" will capitalize "This".
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("hello there. how are you?  fine! pi is 3.14");
+    /// assert_eq!(s.capitalize_sentences(), "Hello there. How are you?  Fine! Pi is 3.14");
+    ///
+    /// // Opening quotes are skipped and closing quotes end the sentence
+    /// let s = local_str!(r#"hi."yes" no. "quoted." next"#);
+    /// assert_eq!(s.capitalize_sentences(), r#"Hi."yes" no. "Quoted." Next"#);
+    /// let s = local_str!("(really?) sure. 3 items. (one) two");
+    /// assert_eq!(s.capitalize_sentences(), "(Really?) Sure. 3 items. (One) two");
+    ///
+    /// let s = local_str!("ﬁne. ßig. ǆungla");
+    /// assert_eq!(s.capitalize_sentences(), "Fine. Ssig. ǅungla");
+    ///
+    /// let s = local_str!("Already. Capitalized.");
+    /// assert!(s.capitalize_sentences().is_static());
+    /// ```
+    pub fn capitalize_sentences(&self) -> Self
+    where
+        HEAP: Clone + for<'a> From<&'a str>,
+    {
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, self.len());
+        let mut changed = false;
+        let mut capitalize = true;
+        let mut terminated = false;
+
+        for (idx, ch) in self.char_indices() {
+            if ch.is_whitespace() {
+                capitalize |= terminated;
+            } else if ch.is_numeric() {
+                capitalize = false;
+            } else if capitalize && ch.is_alphabetic() {
+                capitalize = false;
+                let title = to_titlecase(ch);
+
+                if !title.clone().eq(core::iter::once(ch)) {
+                    // Nothing was written until now, so catch up on everything prior to this char
+                    if !changed {
+                        builder.str_write(&self[..idx]);
+                        changed = true;
+                    }
+                    for ch in title {
+                        builder.char_write(ch);
+                    }
+                    terminated = false;
+                    continue;
+                }
+            }
+
+            if changed {
+                builder.char_write(ch);
+            }
+            terminated = match ch {
+                '.' | '!' | '?' => true,
+                // Closing quotes and brackets after a terminator still end the sentence
+                '"' | '\'' | ')' | ']' | '}' | '”' | '’' | '»' => terminated,
+                _ => false,
+            };
+        }
+
+        if changed {
+            builder_into!(builder, buffer)
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Replaces all matches of `from` with `to`, like [replace](str::replace), but returns a
+    /// borrowed view of this string if `from` does not occur (no allocation or clone of any kind).
+    /// This is useful when only a [str] is needed afterwards.
+    /// ```
+    /// use std::borrow::Cow;
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("Hello world!");
+    /// assert!(matches!(s.replace_cow("cat", "dog"), Cow::Borrowed("Hello world!")));
+    /// assert_eq!(s.replace_cow("world", "there"), "Hello there!");
+    /// ```
+    #[inline]
+    pub fn replace_cow(&self, from: &str, to: &str) -> Cow<'_, str> {
+        if self.contains(from) {
+            Cow::Owned(self.replace(from, to))
+        } else {
+            Cow::Borrowed(self)
+        }
+    }
+
+    /// Joins `segment` to the end of this string with exactly one `/` between them, ignoring any
+    /// redundant slashes at the boundary (see [flex_join_path] for the full semantics).
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("https://example.com/").join_path("/index.html");
+    /// assert_eq!(s, "https://example.com/index.html");
+    /// assert_eq!(local_str!("a").join_path("b"), "a/b");
+    /// ```
+    #[inline]
+    pub fn join_path(&self, segment: &str) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        flex_join_path(&[self.as_str(), segment])
+    }
+
+    /// Replaces all matches of `from` with `to`, where both are the same length in bytes (for
+    /// example, masking a token with the same number of `X`s). As the length of the result is
+    /// known up front, it is built with exact capacity in a single pass. If `from` does not occur,
+    /// a clone of this string is returned. To overwrite a uniquely owned heap string in place,
+    /// use `replace_same_len_in_place` on [LocalStr] or [SharedStr].
+    ///
+    /// # Panics
+    /// Panics if `from` and `to` have different lengths.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("PIN: 1234").replace_same_len("1234", "XXXX");
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "PIN: XXXX");
+    /// assert!(local_str!("no pin").replace_same_len("1234", "XXXX").is_static());
+    /// ```
+    pub fn replace_same_len(&self, from: &str, to: &str) -> Self
+    where
+        HEAP: Clone + for<'a> From<&'a str>,
+    {
+        assert_eq!(from.len(), to.len(), "replacement must be the same length");
+
+        if !self.contains(from) {
+            return self.clone();
+        }
+
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, self.len());
+        let mut last = 0;
+
+        for (idx, _) in self.match_indices(from) {
+            builder.str_write(&self[last..idx]);
+            builder.str_write(to);
+            last = idx + from.len();
+        }
+
+        builder.str_write(&self[last..]);
+        builder_into!(builder, buffer)
+    }
+
+    /// Escapes this string for use as the contents of a JSON string (surrounding quotes are not
+    /// added). Quotes, backslashes, and control chars are escaped, and all other chars are left
+    /// as is. If nothing needs escaping, a clone of this string is returned.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("say \"hi\"\n\x01").escape_json();
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, r#"say \"hi\"\n\u0001"#);
+    /// assert!(local_str!("clean_identifier").escape_json().is_static());
+    /// ```
+    pub fn escape_json(&self) -> Self
+    where
+        HEAP: Clone + for<'a> From<&'a str>,
+    {
+        let needs_escape = |b: u8| b == b'"' || b == b'\\' || b < 0x20;
+
+        if !self.bytes().any(needs_escape) {
+            return self.clone();
+        }
+
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, self.len() + 2);
+        let mut start = 0;
+
+        // All escaped chars are ASCII, so every index found is on a char boundary
+        for (idx, b) in self.bytes().enumerate().filter(|&(_, b)| needs_escape(b)) {
+            builder.str_write(&self[start..idx]);
+            match b {
+                b'"' => builder.str_write("\\\""),
+                b'\\' => builder.str_write("\\\\"),
+                b'\n' => builder.str_write("\\n"),
+                b'\r' => builder.str_write("\\r"),
+                b'\t' => builder.str_write("\\t"),
+                0x08 => builder.str_write("\\b"),
+                0x0C => builder.str_write("\\f"),
+                _ => {
+                    const HEX: &[u8; 16] = b"0123456789abcdef";
+                    builder.str_write("\\u00");
+                    builder.char_write(HEX[(b >> 4) as usize] as char);
+                    builder.char_write(HEX[(b & 0xF) as usize] as char);
+                }
+            }
+            start = idx + 1;
+        }
+        builder.str_write(&self[start..]);
+
+        builder_into!(builder, buffer)
+    }
+
+    /// Returns a new string with `f` applied to each char. Since `f` is called only once per char,
+    /// any unchanged leading chars are copied whole once the first changed char is found. If `f`
+    /// leaves every char unchanged, a clone of this string is returned.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let rot13 = |ch: char| match ch {
+    ///     'a'..='m' | 'A'..='M' => (ch as u8 + 13) as char,
+    ///     'n'..='z' | 'N'..='Z' => (ch as u8 - 13) as char,
+    ///     _ => ch,
+    /// };
+    ///
+    /// let s = local_str!("Hello, world!").map_chars(rot13);
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "Uryyb, jbeyq!");
+    /// assert!(local_str!("123 ☃").map_chars(rot13).is_static());
+    /// ```
+    pub fn map_chars(&self, mut f: impl FnMut(char) -> char) -> Self
+    where
+        HEAP: Clone + for<'a> From<&'a str>,
+    {
+        let mut chars = self.char_indices();
+
+        let (idx, mapped) = match chars.find_map(|(idx, ch)| {
+            let mapped = f(ch);
+            if mapped != ch {
+                Some((idx, mapped))
+            } else {
+                None
+            }
+        }) {
+            Some(first_changed) => first_changed,
+            None => return self.clone(),
+        };
+
+        // We estimate capacity based on previous string, but if chars change length this might be wrong
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, self.len());
+
+        builder.str_write(&self[..idx]);
+        builder.char_write(mapped);
+        for (_, ch) in chars {
+            builder.char_write(f(ch));
+        }
+
+        builder_into!(builder, buffer)
+    }
+
+    /// Returns a new string with every occurrence of the char `from` replaced by `to`. This is more
+    /// efficient than a general replace as the unchanged segments between matches are copied
+    /// whole. If `from` does not occur in the string, a clone of this string is returned.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("flexstr/storage/inline").replace_char('/', '.');
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "flexstr.storage.inline");
+    /// assert_eq!(local_str!("a→b→c").replace_char('→', '>'), "a>b>c");
+    /// assert!(local_str!("no slashes").replace_char('/', '.').is_static());
+    /// ```
+    pub fn replace_char(&self, from: char, to: char) -> Self
+    where
+        HEAP: Clone + for<'a> From<&'a str>,
+    {
+        if !self.contains(from) {
+            return self.clone();
+        }
+
+        // Exact when both chars encode to the same length, which is the common (ASCII) case
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, self.len());
+
+        for (idx, segment) in self.split(from).enumerate() {
+            if idx > 0 {
+                builder.char_write(to);
+            }
+            builder.str_write(segment);
+        }
+
+        builder_into!(builder, buffer)
+    }
+
+    /// Repeats this string up to `n` times, but only as many complete times as fit in `max_len`
+    /// bytes. Unlike [repeat_n](Repeat::repeat_n), the result size is bounded regardless of `n`,
+    /// so this never panics or overflows on untrusted inputs. If this string is empty or is
+    /// repeated zero times, an empty static string is returned.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("=").repeat_saturating(usize::MAX, 10);
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "==========");
+    /// assert_eq!(local_str!("ab").repeat_saturating(3, 5), "abab");
+    /// assert_eq!(local_str!("ab").repeat_saturating(2, 100), "abab");
+    /// ```
+    pub fn repeat_saturating(&self, n: usize, max_len: usize) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        let reps = match self.len() {
+            0 => 0,
+            len => n.min(max_len / len),
+        };
+        if reps == 0 {
+            return Self::from_static("");
+        }
+
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, self.len() * reps);
+        for _ in 0..reps {
+            builder.str_write(self);
+        }
+        builder_into!(builder, buffer)
+    }
+
+    /// Repeats this string until the result is exactly `width` chars long, truncating the final
+    /// repetition if needed (unlike [repeat_n](Repeat::repeat_n), which repeats a given number of
+    /// times). If this string is empty or `width` is zero, an empty static string is returned.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("ab").repeat_to_width(5);
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "ababa");
+    /// assert_eq!(local_str!("─☃").repeat_to_width(3), "─☃─");
+    /// assert!(local_str!("").repeat_to_width(5).is_static());
+    /// ```
+    pub fn repeat_to_width(&self, width: usize) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        let char_count = self.chars().count();
+        if char_count == 0 || width == 0 {
+            return Self::from_static("");
+        }
+
+        let reps = width / char_count;
+        let rest = match self.char_indices().nth(width % char_count) {
+            Some((idx, _)) => &self[..idx],
+            None => "",
+        };
+
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, self.len() * reps + rest.len());
+        for _ in 0..reps {
+            builder.str_write(self);
+        }
+        builder.str_write(rest);
+        builder_into!(builder, buffer)
+    }
+
+    /// Returns a new string containing at most the first `n` chars of this string. The string is
+    /// only scanned up to the `n`th char, and a codepoint is never split. If the string has `n` or
+    /// fewer chars, a clone of this string is returned. As with
+    /// [split_at_flex](FlexStr::split_at_flex), a static source will result in a static string.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("snowmen ☃☃☃☃").take_chars(9);
+    /// assert!(s.is_static());
+    /// assert_eq!(s, "snowmen ☃");
+    /// assert_eq!(local_str!("short").take_chars(10), "short");
+    /// ```
+    #[inline]
+    pub fn take_chars(&self, n: usize) -> Self
+    where
+        HEAP: Clone + for<'a> From<&'a str>,
+    {
+        match self.char_indices().nth(n) {
+            Some((end, _)) => self.substr_of(&self[..end]),
+            None => self.clone(),
+        }
+    }
+
+    /// Truncates this string for display if it has more than `max_chars` chars. In that case, as
+    /// many leading chars as possible are kept such that the result, with `ellipsis` appended, is
+    /// `max_chars` chars long (if `ellipsis` itself is longer than `max_chars`, only `ellipsis` is
+    /// returned). If the string already fits, a clone of this string is returned.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("a very long title").truncate_ellipsis(12, "…");
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "a very long…");
+    /// assert_eq!(local_str!("snowmen ☃☃☃☃").truncate_ellipsis(10, "..."), "snowmen...");
+    /// assert!(local_str!("short").truncate_ellipsis(5, "…").is_static());
+    /// ```
+    pub fn truncate_ellipsis(&self, max_chars: usize, ellipsis: &str) -> Self
+    where
+        HEAP: Clone + for<'a> From<&'a str>,
+    {
+        // Don't count further than needed to know if we exceed the maximum
+        if self.chars().nth(max_chars).is_none() {
+            return self.clone();
+        }
+
+        let keep = max_chars.saturating_sub(ellipsis.chars().count());
+        let end = self
+            .char_indices()
+            .nth(keep)
+            .map_or(self.len(), |(idx, _)| idx);
+
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, end + ellipsis.len());
+        builder.str_write(&self[..end]);
+        builder.str_write(ellipsis);
+        builder_into!(builder, buffer)
+    }
+
+    /// Returns a new string with all but the first `keep_start` and last `keep_end` chars replaced
+    /// by `mask`. One `mask` char is used per replaced char, so the char count is unchanged. If
+    /// nothing would be replaced, a clone of this string is returned.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("sk_live_1234567890").redact(3, 4, '*');
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "sk_***********7890");
+    /// assert!(local_str!("short").redact(3, 2, '*').is_static());
+    /// ```
+    pub fn redact(&self, keep_start: usize, keep_end: usize, mask: char) -> Self
+    where
+        HEAP: Clone + for<'a> From<&'a str>,
+    {
+        let char_count = self.chars().count();
+        if keep_start.saturating_add(keep_end) >= char_count {
+            return self.clone();
+        }
+
+        let masked = char_count - keep_start - keep_end;
+        let start = self
+            .char_indices()
+            .nth(keep_start)
+            .map_or(0, |(idx, _)| idx);
+        let end = self
+            .char_indices()
+            .nth(keep_start + masked)
+            .map_or(self.len(), |(idx, _)| idx);
+
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, start + mask.len_utf8() * masked + self.len() - end);
+
+        builder.str_write(&self[..start]);
+        for _ in 0..masked {
+            builder.char_write(mask);
+        }
+        builder.str_write(&self[end..]);
+
+        builder_into!(builder, buffer)
+    }
+
+    /// Returns a new string with the given byte range replaced by `replacement`. The range follows
+    /// the same rules as [String::replace_range]. Since [FlexStr] is immutable, the original string
+    /// is unchanged.
+    ///
+    /// # Panics
+    /// Panics if the start or end of the range are not on a char boundary, or if they are out of
+    /// bounds.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("Hello world!");
+    /// let s2 = s.replace_range(6..11, "there");
+    /// assert!(s2.is_inline());
+    /// assert_eq!(s2, "Hello there!");
+    /// assert_eq!(s.replace_range(..5, "Goodbye"), "Goodbye world!");
+    /// assert_eq!(s.replace_range(5.., ""), "Hello");
+    /// assert_eq!(s, "Hello world!");
+    /// ```
+    pub fn replace_range(&self, range: impl RangeBounds<usize>, replacement: &str) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n
+                .checked_add(1)
+                .expect("attempted to index str from after maximum usize"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n
+                .checked_add(1)
+                .expect("attempted to index str up to maximum usize"),
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => self.len(),
+        };
+        assert!(start <= end, "range start must not be greater than end");
+
+        // Slicing will panic if either index is not on a char boundary or is out of bounds
+        let (prefix, suffix) = (&self[..start], &self[end..]);
+
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, prefix.len() + replacement.len() + suffix.len());
+        builder.str_write(prefix);
+        builder.str_write(replacement);
+        builder.str_write(suffix);
+        builder_into!(builder, buffer)
+    }
+
+    /// Prepends `prefix` to each line that is not blank (empty or only whitespace). Line endings
+    /// are preserved. If no lines would change, a clone of this string is returned.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("fn main() {\n\n    run();\n}\n");
+    /// assert_eq!(s.indent("  "), "  fn main() {\n\n      run();\n  }\n");
+    /// assert!(local_str!("\n  \n").indent("  ").is_static());
+    /// ```
+    pub fn indent(&self, prefix: &str) -> Self
+    where
+        HEAP: Clone + for<'a> From<&'a str>,
+    {
+        let lines = self.lines().filter(|line| !is_blank(line)).count();
+        if lines == 0 || prefix.is_empty() {
+            return self.clone();
+        }
+
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, self.len() + prefix.len() * lines);
+
+        for line in self.split_inclusive('\n') {
+            if !is_blank(line) {
+                builder.str_write(prefix);
+            }
+            builder.str_write(line);
+        }
+
+        builder_into!(builder, buffer)
+    }
+
+    /// Removes the longest common leading whitespace from every line. Blank lines (empty or only
+    /// whitespace) are ignored when calculating the common whitespace, and are only trimmed if they
+    /// start with it. Different whitespace chars (such as tabs and spaces) are not considered equal.
+    /// If there is no common leading whitespace, a clone of this string is returned.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("    fn main() {\n\n        run();\n    }\n");
+    /// assert_eq!(s.dedent(), "fn main() {\n\n    run();\n}\n");
+    /// assert!(local_str!("a\n  b").dedent().is_static());
+    /// ```
+    pub fn dedent(&self) -> Self
+    where
+        HEAP: Clone + for<'a> From<&'a str>,
+    {
+        let mut margin: Option<&str> = None;
+
+        for line in self.lines().filter(|line| !is_blank(line)) {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            margin = Some(match margin {
+                Some(margin) => &margin[..common_prefix_len(margin, indent)],
+                None => indent,
+            });
+        }
+
+        match margin {
+            Some(margin) if !margin.is_empty() => {
+                let mut buffer = buffer_new!(SIZE);
+                let mut builder = builder_new!(buffer, self.len());
+
+                for line in self.split_inclusive('\n') {
+                    builder.str_write(line.strip_prefix(margin).unwrap_or(line));
+                }
+
+                builder_into!(builder, buffer)
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Converts this string to its Unicode default case folded form, suitable for use as a key in
+    /// caseless comparisons (for example, "Straße" and "STRASSE" both fold to "strasse"). This is
+    /// distinct from lowercasing, which does not map all case variants to the same string. If
+    /// folding would not change the string, a clone of this string is returned.
+    ///
+    /// Requires the `casefold` feature.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert_eq!(local_str!("Straße").to_casefold(), local_str!("STRASSE").to_casefold());
+    /// assert!(local_str!("strasse").to_casefold().is_static());
+    /// ```
+    #[cfg(feature = "casefold")]
+    pub fn to_casefold(&self) -> Self
+    where
+        HEAP: Clone + for<'a> From<&'a str>,
+    {
+        if self.chars().default_case_fold().eq(self.chars()) {
+            return self.clone();
+        }
+
+        // We estimate capacity based on previous string, but if not ASCII this might be wrong
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, self.len());
+
+        for ch in self.chars().default_case_fold() {
+            builder.char_write(ch);
+        }
+
+        builder_into!(builder, buffer)
+    }
+
+    /// Converts this string to Unicode title case. Words are found using Unicode word boundaries
+    /// (UAX #29), the first character of each word is mapped to title case and the remaining
+    /// characters are lowercased (so "1ST" becomes "1st", not "1St"). Unlike uppercasing, title
+    /// casing maps digraphs and ligatures such as `ǆ` and `ﬁ` to `ǅ` and `Fi` (not `Ǆ` and `FI`)
+    /// at the start of a word.
+    ///
+    /// Requires the `title_case` feature.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert_eq!(local_str!("hello wORLD, it's 1ST").to_title_case(), "Hello World, It's 1st");
+    /// assert_eq!(local_str!("ǆungla").to_title_case(), "ǅungla");
+    /// assert_eq!(local_str!("émile zola").to_title_case(), "Émile Zola");
+    /// assert_eq!(local_str!("ßtraße").to_title_case(), "Sstraße");
+    /// assert_eq!(local_str!("ﬁnd me").to_title_case(), "Find Me");
+    /// assert_eq!(local_str!("ᾳx").to_title_case(), "ᾼx");
+    /// ```
+    #[cfg(feature = "title_case")]
+    pub fn to_title_case(&self) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        // We estimate capacity based on previous string, but if not ASCII this might be wrong
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, self.len());
+
+        for word in self.split_word_bounds() {
+            let mut chars = word.chars();
+
+            if let Some(first) = chars.next() {
+                for ch in to_titlecase(first) {
+                    builder.char_write(ch);
+                }
+            }
+
+            for ch in chars.flat_map(char::to_lowercase) {
+                builder.char_write(ch);
+            }
+        }
+
+        builder_into!(builder, buffer)
+    }
+
+    /// Returns this string in its canonical storage form. A heap based string that is short enough
+    /// to be inlined (for example, one created via [from_ref_heap](FlexStr::from_ref_heap)) will be
+    /// copied into an inline string and the heap reference dropped. Static and inline strings, and
+    /// heap strings too long to be inlined, are returned unchanged.
+    ///
+    /// Operations in this crate that produce a shorter string from a longer one (such as
+    /// [split_at_flex](FlexStr::split_at_flex)) always return canonical storage already.
+    /// ```
+    /// use flexstr::LocalStr;
+    ///
+    /// let s = LocalStr::from_ref_heap("short");
+    /// assert!(s.is_heap());
+    /// let s = s.canonicalize();
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "short");
+    /// ```
+    #[inline]
+    pub fn canonicalize(self) -> Self {
+        if self.is_heap() && self.len() <= SIZE {
+            // SAFETY: We just verified the length will fit inline
+            unsafe { Self::try_inline(self.as_str()).unwrap_unchecked() }
+        } else {
+            self
+        }
+    }
+
+    /// Clones this string into its [canonical](FlexStr::canonicalize) storage form. A heap based
+    /// string that is short enough to be inlined is copied into a new inline string instead of
+    /// incrementing the reference count, otherwise this is a normal clone. Replacing the entries
+    /// of a cache with their canonical clones will release heap storage that is no longer needed.
+    /// ```
+    /// use flexstr::{local_str, LocalStr};
+    ///
+    /// let s = LocalStr::from_ref_heap("short");
+    /// let s2 = s.clone_canonical();
+    /// assert!(s2.is_inline());
+    /// assert_eq!(s, s2);
+    ///
+    /// let s = local_str!("This is a string literal that is too long to be inlined");
+    /// assert!(s.clone_canonical().is_static());
+    /// ```
+    #[inline]
+    pub fn clone_canonical(&self) -> Self
+    where
+        HEAP: Clone,
+    {
+        if self.is_heap() && self.len() <= SIZE {
+            // SAFETY: We just verified the length will fit inline
+            unsafe { Self::try_inline(self.as_str()).unwrap_unchecked() }
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Transforms this string with `f`, returning the result in [canonical](FlexStr::canonicalize)
+    /// storage form.
+    /// ```
+    /// use flexstr::{local_str, LocalStr};
+    ///
+    /// let s = local_str!("name").map_str(|s| LocalStr::from_ref_heap(s.trim()));
+    /// assert!(s.is_inline());
+    /// assert_eq!(s, "name");
+    /// ```
+    #[inline]
+    pub fn map_str(&self, f: impl FnOnce(&str) -> Self) -> Self {
+        f(self).canonicalize()
+    }
+
+    /// Transforms this string with `f`, which should return `None` to signal the string does not
+    /// need to change. In that case, a clone of this string is returned instead (which means a
+    /// static string literal remains static), otherwise the result is returned in
+    /// [canonical](FlexStr::canonicalize) storage form.
+    /// ```
+    /// use flexstr::{local_str, LocalStr};
+    ///
+    /// let strip_dot = |s: &str| s.strip_suffix('.').map(LocalStr::from_ref);
+    ///
+    /// assert_eq!(local_str!("Sentence.").map_if_changed(strip_dot), "Sentence");
+    /// assert!(local_str!("Sentence").map_if_changed(strip_dot).is_static());
+    /// ```
+    #[inline]
+    pub fn map_if_changed(&self, f: impl FnOnce(&str) -> Option<Self>) -> Self
+    where
+        HEAP: Clone,
+    {
+        match f(self) {
+            Some(s) => s.canonicalize(),
+            None => self.clone(),
+        }
+    }
+
+    /// Splits this string by `pat` with the same semantics as
+    /// [split_inclusive](str::split_inclusive): each item includes its terminating match of `pat`,
+    /// except possibly the last item if the string does not end with a match. As with
+    /// [split_at_flex](FlexStr::split_at_flex), a static source will result in static items.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let stmts: Vec<_> = local_str!("a = 1; b = 2; c").split_inclusive_flex("; ").collect();
+    /// assert_eq!(stmts, ["a = 1; ", "b = 2; ", "c"]);
+    /// assert!(stmts.iter().all(|stmt| stmt.is_static()));
+    /// ```
+    #[inline]
+    pub fn split_inclusive_flex<'a>(&'a self, pat: &'a str) -> impl Iterator<Item = Self> + 'a
+    where
+        HEAP: for<'b> From<&'b str>,
+    {
+        self.split_inclusive(pat).map(|part| self.substr_of(part))
+    }
+
+    /// Returns the lines of this string, with the same semantics as [lines](str::lines), paired
+    /// with their 1-based line numbers (as used in diagnostics). As with
+    /// [split_at_flex](FlexStr::split_at_flex), a static source will result in static lines.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let lines: Vec<_> = local_str!("fn main() {\r\n}\n").enumerate_lines().collect();
+    /// assert_eq!(lines, [(1, local_str!("fn main() {")), (2, local_str!("}"))]);
+    /// assert!(lines.iter().all(|(_, line)| line.is_static()));
+    /// ```
+    #[inline]
+    pub fn enumerate_lines(&self) -> impl Iterator<Item = (usize, Self)> + '_
+    where
+        HEAP: for<'b> From<&'b str>,
+    {
+        self.lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, self.substr_of(line)))
+    }
+
+    /// Groups consecutive chars of this string that share the same `key` into runs, returning
+    /// each maximal run as a new string. As with [split_at_flex](FlexStr::split_at_flex), a static
+    /// source will result in static items.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let runs: Vec<_> = local_str!("abc123de!").group_runs(char::is_alphabetic).collect();
+    /// assert_eq!(runs, ["abc", "123", "de", "!"]);
+    /// assert!(runs.iter().all(|run| run.is_static()));
+    ///
+    /// let runs: Vec<_> = local_str!("aaabcc").group_runs(|ch| ch).collect();
+    /// assert_eq!(runs, ["aaa", "b", "cc"]);
+    /// ```
+    #[inline]
+    pub fn group_runs<'a, K: PartialEq>(
+        &'a self,
+        mut key: impl FnMut(char) -> K + 'a,
+    ) -> impl Iterator<Item = Self> + 'a
+    where
+        HEAP: for<'b> From<&'b str>,
+    {
+        let mut rest = self.as_str();
+
+        iter::from_fn(move || {
+            let mut chars = rest.char_indices();
+            let (_, first) = chars.next()?;
+            let run_key = key(first);
+            let end = chars
+                .find(|&(_, ch)| key(ch) != run_key)
+                .map_or(rest.len(), |(idx, _)| idx);
+
+            let (run, remainder) = rest.split_at(end);
+            rest = remainder;
+            Some(self.substr_of(run))
+        })
+    }
+
+    /// Returns each maximal run of ASCII digits (`0`-`9`) in this string as a new string. Only
+    /// digits are included: a leading sign or a decimal point ends a run, so "-1.5" yields "1" and
+    /// "5". As with [split_at_flex](FlexStr::split_at_flex), a static source will result in static
+    /// items.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let nums: Vec<_> = local_str!("item42-7").number_runs().collect();
+    /// assert_eq!(nums, ["42", "7"]);
+    /// assert!(nums.iter().all(|num| num.is_static()));
+    /// ```
+    #[inline]
+    pub fn number_runs(&self) -> impl Iterator<Item = Self> + '_
+    where
+        HEAP: for<'b> From<&'b str>,
+    {
+        self.split(|ch: char| !ch.is_ascii_digit())
+            .filter(|run| !run.is_empty())
+            .map(|run| self.substr_of(run))
+    }
+
+    /// Returns the first maximal run of ASCII digits in this string (see
+    /// [number_runs](FlexStr::number_runs)), or `None` if there are no digits.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// assert_eq!(local_str!("v12.3").first_number().unwrap(), "12");
+    /// assert_eq!(local_str!("none").first_number(), None);
+    /// ```
+    #[inline]
+    pub fn first_number(&self) -> Option<Self>
+    where
+        HEAP: for<'b> From<&'b str>,
+    {
+        self.number_runs().next()
+    }
+
+    /// Splits this string by `delim`, treating any char preceded by `escape` literally (so an
+    /// escaped `delim` does not split, and the `escape` chars are removed from the items). A
+    /// trailing `escape` with nothing after it is kept as is. As with [split](str::split), `n`
+    /// delimiters always yield `n + 1` items. Items that contained no escapes are returned the
+    /// same way as [split_at_flex](FlexStr::split_at_flex) (a static source will result in static
+    /// items), while items that needed unescaping are built new (and inlined when possible).
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let fields: Vec<_> = local_str!(r"a\,b,c,d\\").split_escaped(',', '\\').collect();
+    /// assert_eq!(fields, ["a,b", "c", r"d\"]);
+    /// assert!(fields[1].is_static());
+    /// assert!(fields[0].is_inline());
+    /// ```
+    pub fn split_escaped(&self, delim: char, escape: char) -> impl Iterator<Item = Self> + '_
+    where
+        HEAP: for<'b> From<&'b str>,
+    {
+        let mut rest = Some(self.as_str());
+
+        iter::from_fn(move || {
+            let s = rest?;
+            let mut chars = s.char_indices();
+            let mut has_escape = false;
+            let mut end = s.len();
+            rest = None;
+
+            while let Some((idx, ch)) = chars.next() {
+                if ch == escape {
+                    has_escape = true;
+                    chars.next();
+                } else if ch == delim {
+                    end = idx;
+                    rest = Some(&s[idx + ch.len_utf8()..]);
+                    break;
+                }
+            }
+
+            let item = &s[..end];
+            if !has_escape {
+                return Some(self.substr_of(item));
+            }
+
+            let mut buffer = buffer_new!(SIZE);
+            let mut builder = builder_new!(buffer, item.len());
+            let mut chars = item.chars();
+
+            while let Some(ch) = chars.next() {
+                let ch = match ch == escape {
+                    true => chars.next().unwrap_or(escape),
+                    false => ch,
+                };
+                builder.char_write(ch);
+            }
+
+            Some(builder_into!(builder, buffer))
+        })
+    }
+
+    /// Returns this string with leading and trailing whitespace removed, with the same semantics
+    /// as [trim](str::trim). As with [split_at_flex](FlexStr::split_at_flex), a static source will
+    /// result in a static string (pointing into the same literal), and an all whitespace string
+    /// results in an empty static string.
+    /// ```
+    /// use flexstr::{local_str, LocalStr};
+    ///
+    /// let s = local_str!("  padded\t\n").trim_flex();
+    /// assert!(s.is_static());
+    /// assert_eq!(s, "padded");
+    ///
+    /// let s = LocalStr::from_ref_heap(" \t\n ").trim_flex();
+    /// assert!(s.is_static());
+    /// assert_eq!(s, LocalStr::default());
+    /// ```
+    #[inline]
+    pub fn trim_flex(&self) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        self.substr_of(self.trim())
+    }
+
+    /// Returns this string with leading whitespace removed, with the same semantics as
+    /// [trim_start](str::trim_start). As with [split_at_flex](FlexStr::split_at_flex), a static
+    /// source will result in a static string.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("  padded  ").trim_start_flex();
+    /// assert!(s.is_static());
+    /// assert_eq!(s, "padded  ");
+    /// ```
+    #[inline]
+    pub fn trim_start_flex(&self) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        self.substr_of(self.trim_start())
+    }
+
+    /// Returns this string with trailing whitespace removed, with the same semantics as
+    /// [trim_end](str::trim_end). As with [split_at_flex](FlexStr::split_at_flex), a static
+    /// source will result in a static string.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("  padded  ").trim_end_flex();
+    /// assert!(s.is_static());
+    /// assert_eq!(s, "  padded");
+    /// ```
+    #[inline]
+    pub fn trim_end_flex(&self) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        self.substr_of(self.trim_end())
+    }
+
+    /// Splits this string by `pat`, with the same semantics as [split](str::split), returning
+    /// each item as a new string. The storage of each item is chosen the same way as
+    /// [split_at_flex](FlexStr::split_at_flex): static sources yield static sub-slices and inline
+    /// sources yield inline items, so neither ever allocates. Heap storage is an exact sized
+    /// `Rc<str>`/`Arc<str>` that cannot refer to part of itself, so items of a heap source are
+    /// inlined when short enough and otherwise allocated (sharing would require an offset and
+    /// length in every heap string, making all strings larger).
+    /// ```
+    /// use flexstr::{local_str, LocalStr};
+    ///
+    /// let items: Vec<_> = local_str!("a,b,,c").split_flex(',').collect();
+    /// assert_eq!(items, ["a", "b", "", "c"]);
+    /// assert!(items.iter().all(|item| item.is_static()));
+    ///
+    /// let s = LocalStr::from_ref_heap("key=value");
+    /// let items: Vec<_> = s.split_flex('=').collect();
+    /// assert_eq!(items, ["key", "value"]);
+    /// assert!(items.iter().all(|item| item.is_inline()));
+    /// ```
+    #[inline]
+    pub fn split_flex(&self, pat: char) -> impl Iterator<Item = Self> + '_
+    where
+        HEAP: for<'b> From<&'b str>,
+    {
+        self.split(pat).map(|part| self.substr_of(part))
+    }
+
+    /// Splits this string by `pat`, returning at most `n` items, with the same semantics as
+    /// [splitn](str::splitn) (the last item contains the remainder of the string). As with
+    /// [split_at_flex](FlexStr::split_at_flex), a static source will result in static items.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let parts: Vec<_> = local_str!("GET /index.html HTTP/1.1").splitn_flex(2, " ").collect();
+    /// assert_eq!(parts, ["GET", "/index.html HTTP/1.1"]);
+    /// assert!(parts.iter().all(|part| part.is_static()));
+    /// ```
+    #[inline]
+    pub fn splitn_flex<'a>(&'a self, n: usize, pat: &'a str) -> impl Iterator<Item = Self> + 'a
+    where
+        HEAP: for<'b> From<&'b str>,
+    {
+        self.splitn(n, pat).map(|part| self.substr_of(part))
+    }
+
+    /// Splits this string by `pat` starting from the end, returning at most `n` items, with the
+    /// same semantics as [rsplitn](str::rsplitn) (the last item contains the remainder of the
+    /// string). As with [split_at_flex](FlexStr::split_at_flex), a static source will result in
+    /// static items.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let parts: Vec<_> = local_str!("archive.tar.gz").rsplitn_flex(2, ".").collect();
+    /// assert_eq!(parts, ["gz", "archive.tar"]);
+    /// ```
+    #[inline]
+    pub fn rsplitn_flex<'a>(&'a self, n: usize, pat: &'a str) -> impl Iterator<Item = Self> + 'a
+    where
+        HEAP: for<'b> From<&'b str>,
+    {
+        self.rsplitn(n, pat).map(|part| self.substr_of(part))
+    }
+
+    /// Returns the longest prefix this string has in common with `other`. Only whole chars are
+    /// compared, so the prefix never ends partway through a multibyte char. As with
+    /// [split_at_flex](FlexStr::split_at_flex), a static source will result in a static string.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("interstellar").common_prefix("internet");
+    /// assert!(s.is_static());
+    /// assert_eq!(s, "inter");
+    /// // '☃' and '☄' share their first two bytes, but not the whole char
+    /// assert_eq!(local_str!("a☃").common_prefix("a☄"), "a");
+    /// ```
+    #[inline]
+    pub fn common_prefix(&self, other: &str) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        self.substr_of(&self[..common_prefix_len(self, other)])
+    }
+
+    /// Returns the longest suffix this string has in common with `other`. Only whole chars are
+    /// compared, so the suffix never starts partway through a multibyte char. As with
+    /// [split_at_flex](FlexStr::split_at_flex), a static source will result in a static string.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("running").common_suffix("jumping");
+    /// assert!(s.is_static());
+    /// assert_eq!(s, "ing");
+    /// assert_eq!(local_str!("☃x").common_suffix("☄x"), "x");
+    /// ```
+    #[inline]
+    pub fn common_suffix(&self, other: &str) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        self.substr_of(&self[self.len() - common_suffix_len(self, other)..])
+    }
+
+    /// Splits this string on the first occurrence of `sep` and trims whitespace from both the key
+    /// and the value. Returns `None` if the separator is not found. As with
+    /// [split_at_flex](FlexStr::split_at_flex), a static source will result in static key/value
+    /// strings.
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let (key, value) = local_str!("  name = value = more ").parse_kv('=').unwrap();
+    /// assert!(key.is_static() && value.is_static());
+    /// assert_eq!(key, "name");
+    /// assert_eq!(value, "value = more");
+    ///
+    /// assert!(local_str!("no separator").parse_kv('=').is_none());
+    /// ```
+    pub fn parse_kv(&self, sep: char) -> Option<(Self, Self)>
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        let (key, value) = self.split_once(sep)?;
+        Some((self.substr_of(key.trim()), self.substr_of(value.trim())))
+    }
+
+    /// Removes a leading byte order mark (U+FEFF) if present, otherwise this string is cloned. As
+    /// with [split_at_flex](FlexStr::split_at_flex), a static source will result in a static
+    /// string. A string consisting of only the byte order mark results in an empty static string.
+    /// ```
+    /// use flexstr::{local_str, LocalStr};
+    ///
+    /// let s = local_str!("\u{FEFF}name,value").strip_bom();
+    /// assert!(s.is_static());
+    /// assert_eq!(s, "name,value");
+    ///
+    /// let s = LocalStr::from_ref("\u{FEFF}").strip_bom();
+    /// assert!(s.is_static());
+    /// assert_eq!(s, "");
+    ///
+    /// assert_eq!(local_str!("no bom").strip_bom(), "no bom");
+    /// ```
+    pub fn strip_bom(&self) -> Self
+    where
+        HEAP: Clone + for<'a> From<&'a str>,
+    {
+        match self.strip_prefix('\u{FEFF}') {
+            Some("") => Self::from_static(""),
+            Some(rest) => self.substr_of(rest),
+            None => self.clone(),
+        }
+    }
+
+    // Creates a new string from a sub-slice of this one. Static strings stay static (pointing into
+    // the same literal) and all others are inlined if possible, otherwise heap allocated
+    #[inline]
+    fn substr_of(&self, sub: &str) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        match self.try_as_static_str() {
+            Ok(literal) => {
+                // `sub` is always derived from our literal, so this offset is in bounds
+                let start = sub.as_ptr() as usize - literal.as_ptr() as usize;
+                Self::from_static(&literal[start..start + sub.len()])
+            }
+            Err(_) => Self::from_ref(sub),
+        }
+    }
+
+    /// Writes the concatenation of this string and `rhs` into the caller provided `scratch` buffer
+    /// and returns it as a [str], without any allocation. If the concatenation does not fit in
+    /// `scratch`, a [CapacityError] is returned (and `scratch` is left unmodified).
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let mut scratch = [0; 64];
+    /// let s = local_str!("key:").concat_into("value", &mut scratch).unwrap();
+    /// assert_eq!(s, "key:value");
+    ///
+    /// let err = local_str!("key:").concat_into("value", &mut [0; 4]).unwrap_err();
+    /// assert_eq!(err.required, 9);
+    /// ```
+    pub fn concat_into<'b>(
+        &self,
+        rhs: &str,
+        scratch: &'b mut [u8],
+    ) -> Result<&'b str, CapacityError> {
+        let required = self.len() + rhs.len();
+        if required > scratch.len() {
+            return Err(CapacityError {
+                required,
+                capacity: scratch.len(),
+            });
+        }
+
+        let (lhs_buf, rhs_buf) = scratch[..required].split_at_mut(self.len());
+        lhs_buf.copy_from_slice(self.as_bytes());
+        rhs_buf.copy_from_slice(rhs.as_bytes());
+
+        // SAFETY: The buffer contains only the bytes of two valid UTF-8 strings
+        Ok(unsafe { core::str::from_utf8_unchecked(&scratch[..required]) })
+    }
+
+    /// Concatenates `rhs` onto this string (like the `+` operator) only if the resulting length in
+    /// bytes would not exceed `max_len`. Otherwise, a [CapacityError] is returned with the length
+    /// the concatenated string would have required (nothing is allocated in that case).
+    /// ```
+    /// use flexstr::local_str;
+    ///
+    /// let s = local_str!("Hello").try_add(" world", 16).unwrap();
+    /// assert_eq!(s, "Hello world");
+    ///
+    /// let err = s.try_add(" and everyone else", 16).unwrap_err();
+    /// assert_eq!(err.required, 29);
+    /// assert_eq!(err.capacity, 16);
+    /// ```
+    #[inline]
+    pub fn try_add(self, rhs: &str, max_len: usize) -> Result<Self, CapacityError>
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        match self.len().checked_add(rhs.len()) {
+            Some(len) if len <= max_len => Ok(self.add(rhs)),
+            len => Err(CapacityError {
+                required: len.unwrap_or(usize::MAX),
+                capacity: max_len,
+            }),
+        }
+    }
+
+    #[inline]
+    fn add(mut self, rhs: &str) -> Self
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        if rhs.is_empty() {
+            self
+        } else if self.is_empty() {
+            rhs.into()
+        } else {
+            // SAFETY: Marker check is aligned to correct accessed field
+            unsafe {
+                match self.static_str.marker {
+                    StorageType::Static => concat(self.static_str.literal, rhs),
+                    StorageType::Inline => {
+                        let s = &mut self.inline_str;
+
+                        if s.try_concat(rhs) {
+                            self
+                        } else {
+                            concat(s, rhs)
+                        }
+                    }
+                    StorageType::Heap => concat(&self.heap_str.heap, rhs),
+                }
+            }
+        }
+    }
+}
+
+// *** `Rc` and `Arc` specific functions ***
+
+macro_rules! impl_ref_count_heap {
+    ($($heap:ident => $alias:ident),+) => {
+        $(impl<const SIZE: usize, const PAD1: usize, const PAD2: usize>
+            FlexStr<SIZE, PAD1, PAD2, $heap<str>>
+        {
+            #[doc = concat!("Consumes a heap based string and returns the raw pointer and length of the \
+            underlying [`", stringify!($heap), "<str>`] without decrementing the reference count. The \
+            memory will remain valid until it is reclaimed via [from_raw_heap](FlexStr::from_raw_heap). \
+            If this is not a heap based string, the original string is returned as the error.")]
+            /// ```
+            #[doc = concat!("use flexstr::", stringify!($alias), ";")]
+            ///
+            #[doc = concat!("let s = ", stringify!($alias), "::from_ref(\"This is too long to be inlined!!!\");")]
+            /// let (ptr, len) = s.into_raw_heap().unwrap();
+            /// // SAFETY: The pointer and length were just obtained from `into_raw_heap`
+            #[doc = concat!("let s = unsafe { ", stringify!($alias), "::from_raw_heap(ptr, len) };")]
+            /// assert!(s.is_heap());
+            /// assert_eq!(s, "This is too long to be inlined!!!");
+            ///
+            #[doc = concat!("let s = ", stringify!($alias), "::from_ref(\"inlined\");")]
+            /// assert!(s.into_raw_heap().unwrap_err().is_inline());
+            /// ```
+            #[inline]
+            pub fn into_raw_heap(self) -> Result<(*const u8, usize), Self> {
+                if self.is_heap() {
+                    let s = ManuallyDrop::new(self);
+                    // SAFETY: Marker check is aligned to correct accessed field. We take ownership
+                    // of the heap value and `s` is never dropped, so it will not be dropped twice
+                    let heap = unsafe { ptr::read(&s.heap_str.heap) };
+                    let len = heap.len();
+                    Ok(($heap::into_raw(heap).cast::<u8>(), len))
+                } else {
+                    Err(self)
+                }
+            }
+
+            #[doc = concat!("Reclaims a heap based string from the raw pointer and length returned by \
+            [into_raw_heap](FlexStr::into_raw_heap). The reference count of the underlying [`",
+            stringify!($heap), "<str>`] is taken over by the new string.")]
+            ///
+            /// # Safety
+            /// The pointer and length must have been returned by a previous call to
+            /// [into_raw_heap](FlexStr::into_raw_heap) on this same type, and each such pointer may
+            /// only be reclaimed once.
+            #[inline]
+            pub unsafe fn from_raw_heap(ptr: *const u8, len: usize) -> Self {
+                let s = ptr::slice_from_raw_parts(ptr, len) as *const str;
+                Self::from_heap($heap::from_raw(s))
+            }
+
+            /// Replaces the contents of this string with `s`, reusing the existing storage when
+            /// possible. A uniquely owned heap string of the exact same length is overwritten in
+            /// place (heap strings are exact sized, so any other length requires new storage).
+            /// Otherwise, new storage is chosen the same way as [from_ref](FlexStr::from_ref), which
+            /// never allocates if the new contents can be inlined.
+            /// ```
+            #[doc = concat!("use flexstr::", stringify!($alias), ";")]
+            ///
+            #[doc = concat!("let mut s = ", stringify!($alias), "::from_ref(\"This is too long to be inlined!!!\");")]
+            /// let ptr = s.as_ptr();
+            /// s.assign_from("Another string of the same size!!");
+            /// assert_eq!(s.as_ptr(), ptr);
+            /// assert_eq!(s, "Another string of the same size!!");
+            ///
+            /// s.assign_from("inlined");
+            /// assert!(s.is_inline());
+            /// assert_eq!(s, "inlined");
+            /// ```
+            pub fn assign_from(&mut self, s: &str) {
+                if self.len() == s.len() {
+                    if let Some(buffer) = self.heap_as_mut_str() {
+                        // SAFETY: We are replacing valid UTF-8 with valid UTF-8 of the same length
+                        unsafe { buffer.as_bytes_mut() }.copy_from_slice(s.as_bytes());
+                        return;
+                    }
+                }
+
+                *self = Self::from_ref(s);
+            }
+
+            /// Replaces all matches of `from` with `to`, where both are the same length in bytes.
+            /// A uniquely owned heap string is overwritten in place with no allocation, otherwise
+            /// this string is replaced with the result of
+            /// [replace_same_len](FlexStr::replace_same_len).
+            ///
+            /// # Panics
+            /// Panics if `from` and `to` have different lengths.
+            /// ```
+            #[doc = concat!("use flexstr::", stringify!($alias), ";")]
+            ///
+            #[doc = concat!("let mut s = ", stringify!($alias), "::from_ref(\"Card: 1234 5678 1234 5678\");")]
+            /// let ptr = s.as_ptr();
+            /// s.replace_same_len_in_place("1234", "XXXX");
+            /// assert_eq!(s.as_ptr(), ptr);
+            /// assert_eq!(s, "Card: XXXX 5678 XXXX 5678");
+            /// ```
+            pub fn replace_same_len_in_place(&mut self, from: &str, to: &str) {
+                assert_eq!(from.len(), to.len(), "replacement must be the same length");
+
+                if from.is_empty() {
+                    return;
+                }
+
+                match self.heap_as_mut_str() {
+                    Some(buffer) => {
+                        let mut pos = 0;
+
+                        while let Some(idx) = buffer[pos..].find(from) {
+                            let start = pos + idx;
+                            pos = start + to.len();
+                            // SAFETY: A whole match (valid UTF-8) is replaced by valid UTF-8 of
+                            // the same length
+                            let bytes = unsafe { buffer.as_bytes_mut() };
+                            bytes[start..pos].copy_from_slice(to.as_bytes());
+                        }
+                    }
+                    None => *self = self.replace_same_len(from, to),
+                }
+            }
+
+            /// Converts this string to lowercase (see [to_lower](ToCase::to_lower)). If this is
+            /// a uniquely owned heap string and every char lowercases to a single char of the same
+            /// length in bytes (true for most scripts), it is converted in place with no
+            /// allocation. Otherwise this string is replaced by the result of
+            /// [to_lower](ToCase::to_lower) (which does not allocate if the result can be
+            /// inlined).
+            /// ```
+            #[doc = concat!("use flexstr::", stringify!($alias), ";")]
+            ///
+            #[doc = concat!("let mut s = ", stringify!($alias), "::from_ref(\"ΚΑΛΗΜΕΡΑ, This Is Too Long!\");")]
+            /// let ptr = s.as_ptr();
+            /// s.make_lowercase();
+            /// assert_eq!(s.as_ptr(), ptr);
+            /// assert_eq!(s, "καλημερα, this is too long!");
+            /// ```
+            pub fn make_lowercase(&mut self) {
+                if let Some(buffer) = self.heap_as_mut_str() {
+                    if case_map_in_place(buffer, char::to_lowercase) {
+                        return;
+                    }
+                }
+
+                *self = self.to_lower();
+            }
+
+            /// Converts this string to uppercase (see [to_upper](ToCase::to_upper)). If this is
+            /// a uniquely owned heap string and every char uppercases to a single char of the same
+            /// length in bytes, it is converted in place with no allocation. Otherwise this string
+            /// is replaced by the result of [to_upper](ToCase::to_upper) (for example, 'ß' becomes
+            /// "SS").
+            /// ```
+            #[doc = concat!("use flexstr::", stringify!($alias), ";")]
+            ///
+            #[doc = concat!("let mut s = ", stringify!($alias), "::from_ref(\"größe is too long to inline\");")]
+            /// s.make_uppercase();
+            /// assert_eq!(s, "GRÖSSE IS TOO LONG TO INLINE");
+            /// ```
+            pub fn make_uppercase(&mut self) {
+                if let Some(buffer) = self.heap_as_mut_str() {
+                    if case_map_in_place(buffer, char::to_uppercase) {
+                        return;
+                    }
+                }
+
+                *self = self.to_upper();
+            }
+
+            /// Returns a mutable view of the string contents, but only if this is a heap based string
+            /// with no other references to the heap value. Returns `None` for static and inline
+            /// strings or if the heap value is shared, as mutating it would be visible to the other
+            /// holders.
+            /// ```
+            #[doc = concat!("use flexstr::", stringify!($alias), ";")]
+            ///
+            #[doc = concat!("let mut s = ", stringify!($alias), "::from_ref(\"This is too long to be inlined!!!\");")]
+            /// s.heap_as_mut_str().unwrap().make_ascii_uppercase();
+            /// assert_eq!(s, "THIS IS TOO LONG TO BE INLINED!!!");
+            ///
+            /// let s2 = s.clone();
+            /// assert!(s.heap_as_mut_str().is_none());
+            /// ```
+            #[inline]
+            pub fn heap_as_mut_str(&mut self) -> Option<&mut str> {
+                if self.is_heap() {
+                    // SAFETY: Marker check is aligned to correct accessed field
+                    $heap::get_mut(unsafe { &mut self.heap_str.heap })
+                } else {
+                    None
+                }
+            }
+
+            /// Returns `Some(true)` if this is a heap based string with no other references to the
+            /// heap value, `Some(false)` if the heap value is shared, and `None` for static and
+            /// inline strings. When this returns `Some(true)`,
+            /// [heap_as_mut_str](FlexStr::heap_as_mut_str) will succeed.
+            /// ```
+            #[doc = concat!("use flexstr::", stringify!($alias), ";")]
+            ///
+            #[doc = concat!("let s = ", stringify!($alias), "::from_ref(\"This is too long to be inlined!!!\");")]
+            /// assert_eq!(s.heap_is_unique(), Some(true));
+            /// let s2 = s.clone();
+            /// assert_eq!(s.heap_is_unique(), Some(false));
+            #[doc = concat!("assert_eq!(", stringify!($alias), "::from_static(\"static\").heap_is_unique(), None);")]
+            /// ```
+            #[inline]
+            pub fn heap_is_unique(&self) -> Option<bool> {
+                if self.is_heap() {
+                    // SAFETY: Marker check is aligned to correct accessed field
+                    let heap = unsafe { &self.heap_str.heap };
+                    Some($heap::strong_count(heap) == 1 && $heap::weak_count(heap) == 0)
+                } else {
+                    None
+                }
+            }
+        })+
+    };
+}
+
+impl_ref_count_heap!(Rc => LocalStr, Arc => SharedStr);
+
+#[cfg(feature = "std")]
+#[inline]
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+#[inline]
+fn is_blank(line: &str) -> bool {
+    line.trim_start().is_empty()
+}
+
+// Returns the length in bytes of the longest common prefix of both strings (always on a char boundary)
+#[inline]
+fn common_prefix_len(s1: &str, s2: &str) -> usize {
+    s1.char_indices()
+        .zip(s2.chars())
+        .find(|((_, ch1), ch2)| ch1 != ch2)
+        .map_or_else(|| s1.len().min(s2.len()), |((idx, _), _)| idx)
+}
+
+// Iterator over the chars of a title case mapping, returned by `to_titlecase`
+#[derive(Clone)]
+enum ToTitlecase {
+    Special(core::str::Chars<'static>),
+    Char(Option<char>),
+    Upper(core::char::ToUppercase),
+}
+
+impl Iterator for ToTitlecase {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        match self {
+            ToTitlecase::Special(chars) => chars.next(),
+            ToTitlecase::Char(ch) => ch.take(),
+            ToTitlecase::Upper(upper) => upper.next(),
+        }
+    }
+}
+
+// Returns the title case mapping of `ch`. `core` only exposes upper and lower case mappings, so
+// every char whose title case differs from its upper case (per UnicodeData.txt and the
+// unconditional mappings in SpecialCasing.txt) is listed here, and all others are uppercased
+fn to_titlecase(ch: char) -> ToTitlecase {
+    let special = match ch {
+        // Latin digraphs
+        '\u{01C4}'..='\u{01C6}' => "\u{01C5}",
+        '\u{01C7}'..='\u{01C9}' => "\u{01C8}",
+        '\u{01CA}'..='\u{01CC}' => "\u{01CB}",
+        '\u{01F1}'..='\u{01F3}' => "\u{01F2}",
+        // Sharp s and the Latin ligatures
+        '\u{00DF}' => "Ss",
+        '\u{FB00}' => "Ff",
+        '\u{FB01}' => "Fi",
+        '\u{FB02}' => "Fl",
+        '\u{FB03}' => "Ffi",
+        '\u{FB04}' => "Ffl",
+        '\u{FB05}' | '\u{FB06}' => "St",
+        // Armenian ligatures
+        '\u{0587}' => "\u{0535}\u{0582}",
+        '\u{FB13}' => "\u{0544}\u{0576}",
+        '\u{FB14}' => "\u{0544}\u{0565}",
+        '\u{FB15}' => "\u{0544}\u{056B}",
+        '\u{FB16}' => "\u{054E}\u{0576}",
+        '\u{FB17}' => "\u{0544}\u{056D}",
+        // Greek letters with ypogegrammeni (iota subscript) title case to prosgegrammeni, where
+        // upper case would instead append a capital iota
+        '\u{1F80}'..='\u{1F87}' | '\u{1F90}'..='\u{1F97}' | '\u{1FA0}'..='\u{1FA7}' => {
+            return ToTitlecase::Char(char::from_u32(ch as u32 + 8));
+        }
+        '\u{1F88}'..='\u{1F8F}'
+        | '\u{1F98}'..='\u{1F9F}'
+        | '\u{1FA8}'..='\u{1FAF}'
+        | '\u{1FBC}'
+        | '\u{1FCC}'
+        | '\u{1FFC}' => return ToTitlecase::Char(Some(ch)),
+        '\u{1FB3}' => "\u{1FBC}",
+        '\u{1FC3}' => "\u{1FCC}",
+        '\u{1FF3}' => "\u{1FFC}",
+        '\u{1FB2}' => "\u{1FBA}\u{0345}",
+        '\u{1FB4}' => "\u{0386}\u{0345}",
+        '\u{1FC2}' => "\u{1FCA}\u{0345}",
+        '\u{1FC4}' => "\u{0389}\u{0345}",
+        '\u{1FF2}' => "\u{1FFA}\u{0345}",
+        '\u{1FF4}' => "\u{038F}\u{0345}",
+        '\u{1FB7}' => "\u{0391}\u{0342}\u{0345}",
+        '\u{1FC7}' => "\u{0397}\u{0342}\u{0345}",
+        '\u{1FF7}' => "\u{03A9}\u{0342}\u{0345}",
+        // Georgian Mkhedruli letters are their own title case (upper case maps to Mtavruli)
+        '\u{10D0}'..='\u{10FA}' | '\u{10FD}'..='\u{10FF}' => return ToTitlecase::Char(Some(ch)),
+        _ => return ToTitlecase::Upper(ch.to_uppercase()),
+    };
+
+    ToTitlecase::Special(special.chars())
+}
+
+// Maps each char of `s` in place using `f` (a case mapping), but only if every char maps to a
+// single char of the same length in bytes. Returns false (leaving `s` unchanged) otherwise
+fn case_map_in_place<I>(s: &mut str, f: impl Fn(char) -> I) -> bool
+where
+    I: Iterator<Item = char>,
+{
+    let same_len = |ch: char| {
+        let mut mapped = f(ch);
+        match (mapped.next(), mapped.next()) {
+            (Some(new), None) if new.len_utf8() == ch.len_utf8() => Some(new),
+            _ => None,
+        }
+    };
+
+    if !s.chars().all(|ch| same_len(ch).is_some()) {
+        return false;
+    }
+
+    let mut idx = 0;
+    while let Some(ch) = s[idx..].chars().next() {
+        // SAFETY: We verified above this mapping exists
+        let new = unsafe { same_len(ch).unwrap_unchecked() };
+        let len = ch.len_utf8();
+        // SAFETY: A whole char is replaced by a char of the same length in bytes
+        new.encode_utf8(unsafe { &mut s.as_bytes_mut()[idx..idx + len] });
+        idx += len;
+    }
+
+    true
+}
+
+// Returns the length in bytes of the longest common suffix of both strings (always on a char boundary)
+#[inline]
+fn common_suffix_len(s1: &str, s2: &str) -> usize {
+    s1.char_indices()
+        .rev()
+        .zip(s2.chars().rev())
+        .find(|((_, ch1), ch2)| ch1 != ch2)
+        .map_or_else(
+            || s1.len().min(s2.len()),
+            |((idx, ch), _)| s1.len() - idx - ch.len_utf8(),
+        )
+}
+
+#[inline]
+fn concat<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>(
+    s1: &str,
+    s2: &str,
+) -> FlexStr<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: for<'a> From<&'a str>,
+{
+    let mut buffer = buffer_new!(SIZE);
+    let mut builder = builder_new!(buffer, s1.len() + s2.len());
+    builder.str_write(s1);
+    builder.str_write(s2);
+    builder_into!(builder, buffer)
+}
+
+#[inline]
+fn from_iter_str<const SIZE: usize, const PAD1: usize, const PAD2: usize, I, HEAP, U>(
+    iter: I,
+) -> FlexStr<SIZE, PAD1, PAD2, HEAP>
+where
+    I: IntoIterator<Item = U>,
+    HEAP: for<'b> From<&'b str>,
+    U: AsRef<str>,
+{
+    let iter = iter.into_iter();
+
+    // Since `IntoIterator` consumes, we cannot loop over it twice to find lengths of strings
+    // for a good capacity # without cloning it (which might be expensive). Users with cheaply
+    // cloned iterators can use `FlexStr::concat_from_iter` instead
+    let mut buffer = buffer_new!(SIZE);
+    let mut builder = builder_new!(buffer);
+    for s in iter {
+        builder.str_write(s);
+    }
+    builder_into!(builder, buffer)
+}
+
+#[inline]
+fn from_iter_owned<const SIZE: usize, const PAD1: usize, const PAD2: usize, I, F, HEAP, U>(
+    iter: I,
+    f: F,
+) -> FlexStr<SIZE, PAD1, PAD2, HEAP>
+where
+    I: IntoIterator<Item = U>,
+    F: FnOnce(U) -> FlexStr<SIZE, PAD1, PAD2, HEAP>,
+    HEAP: for<'b> From<&'b str>,
+    U: AsRef<str>,
+{
+    let mut iter = iter.into_iter();
+
+    match iter.next() {
+        Some(first) => match iter.next() {
+            // A single owned string can be converted directly instead of copied through a builder
+            None => f(first),
+            Some(second) => from_iter_str([first, second].into_iter().chain(iter)),
+        },
+        None => from_iter_str(iter::empty::<&str>()),
+    }
+}
+
+#[inline]
+fn from_iter_char<const SIZE: usize, const PAD1: usize, const PAD2: usize, I, F, HEAP, U>(
+    iter: I,
+    f: F,
+) -> FlexStr<SIZE, PAD1, PAD2, HEAP>
+where
+    I: IntoIterator<Item = U>,
+    F: Fn(U) -> char,
+    HEAP: for<'b> From<&'b str>,
+{
+    let iter = iter.into_iter();
+    let (lower, _) = iter.size_hint();
+
+    let mut buffer = buffer_new!(SIZE);
+    let mut builder = builder_new!(buffer, lower);
+    for ch in iter {
+        builder.char_write(f(ch));
+    }
+    builder_into!(builder, buffer)
+}
+
+/// Equivalent to the [format](std::fmt::format) function from stdlib. Efficiently creates a native [FlexStr]
+pub fn flex_fmt<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>(
+    args: Arguments<'_>,
+) -> FlexStr<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: for<'a> From<&'a str>,
+{
+    // NOTE: We have a disadvantage to [String] because we cannot call `estimated_capacity()` on args
+    // As such, we cannot assume a given needed capacity - we start with a stack allocated buffer
+    // and only promote to a heap buffer if a write won't fit
+    let mut buffer = buffer_new!(SIZE);
+    let mut builder = builder_new!(buffer);
+    builder
+        .write_fmt(args)
+        .expect("a formatting trait implementation returned an error");
+    builder_into!(builder, buffer)
+}
+
+/// Joins path segments (such as URL path components or hierarchical keys) with exactly one `/`
+/// between each of them, ignoring any redundant slashes at the segment boundaries. A leading slash
+/// on the first segment and a trailing slash on the last segment are preserved, and empty segments
+/// are skipped. This is a plain string operation and does not apply any OS path semantics.
+/// ```
+/// use flexstr::{flex_join_path, LocalStr};
+///
+/// let s: LocalStr = flex_join_path(&["/api/", "/v1", "users/"]);
+/// assert!(s.is_inline());
+/// assert_eq!(s, "/api/v1/users/");
+///
+/// let s: LocalStr = flex_join_path(&["a", "", "/", "b"]);
+/// assert_eq!(s, "a/b");
+/// ```
+pub fn flex_join_path<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP, S>(
+    segments: &[S],
+) -> FlexStr<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: for<'a> From<&'a str>,
+    S: AsRef<str>,
+{
+    let cap = segments.iter().map(|seg| seg.as_ref().len() + 1).sum();
+    let mut buffer = buffer_new!(SIZE);
+    let mut builder = builder_new!(buffer, cap);
+
+    let mut started = false;
+    let mut trailing_slash = false;
+
+    for seg in segments.iter().map(AsRef::as_ref) {
+        if seg.is_empty() {
+            continue;
+        }
+
+        let seg = if started {
+            seg.trim_start_matches('/')
+        } else {
+            seg
+        };
+        let trimmed = seg.trim_end_matches('/');
+
+        if started {
+            // A segment of only slashes adds nothing but may still end the path
+            if trimmed.is_empty() {
+                trailing_slash = true;
+                continue;
+            }
+            builder.char_write('/');
+        }
+
+        builder.str_write(trimmed);
+        trailing_slash = trimmed.len() != seg.len();
+        started = true;
+    }
+
+    if trailing_slash {
+        builder.char_write('/');
+    }
+    builder_into!(builder, buffer)
+}
+
+/// Writes each item to the writer separated by `sep` without building an intermediate joined string.
+/// This is useful when the final destination is a formatter or other writer and the joined value
+/// is never needed in memory.
+/// ```
+/// use flexstr::{local_str, write_joined, LocalStr};
+///
+/// let items: [LocalStr; 3] = [local_str!("a"), "b".into(), local_str!("c")];
+/// let mut s = String::new();
+/// write_joined(&mut s, &items, ", ").unwrap();
+/// assert_eq!(s, "a, b, c");
+/// ```
+pub fn write_joined<W, S>(w: &mut W, items: &[S], sep: &str) -> core::fmt::Result
+where
+    W: Write + ?Sized,
+    S: AsRef<str>,
+{
+    let mut items = items.iter();
+
+    if let Some(first) = items.next() {
+        w.write_str(first.as_ref())?;
+
+        for item in items {
+            w.write_str(sep)?;
+            w.write_str(item.as_ref())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects the strings from `iter` into a boxed slice, interning them as they are collected so that
+/// repeated heap allocated values share a single heap allocation. Order and length are preserved,
+/// only storage is shared. Strings short enough to be inlined are never interned as they are
+/// already copied by value. This is most effective for data with many repeated long values.
+/// ```
+/// use flexstr::{collect_interned, LocalStr};
+///
+/// let column = ["This value is too long to inline", "short", "This value is too long to inline"];
+/// let v: Box<[LocalStr]> = collect_interned(column);
+/// assert_eq!(&*v, &column);
+/// assert!(v[1].is_inline());
+/// assert_eq!(v[0].as_ptr(), v[2].as_ptr());
+/// ```
+pub fn collect_interned<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP, I, S>(
+    iter: I,
+) -> Box<[FlexStr<SIZE, PAD1, PAD2, HEAP>]>
+where
+    HEAP: Clone + Deref<Target = str> + for<'a> From<&'a str>,
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut interned = BTreeSet::new();
+
+    iter.into_iter()
+        .map(|s| {
+            let s = s.as_ref();
+
+            if s.len() <= SIZE {
+                FlexStr::from_ref(s)
+            } else if let Some(existing) = interned.get(s) {
+                FlexStr::clone(existing)
+            } else {
+                let new = FlexStr::from_ref(s);
+                interned.insert(new.clone());
+                new
+            }
+        })
+        .collect()
+}
+
+/// Returns the ASCII lowercased form of the header name `s`, interned in a global pool so that all
+/// header names that are equal ignoring ASCII case share a single heap allocation. Names short
+/// enough to be inlined are returned without using the pool, as they are already copied by value.
+/// The pool is never shrunk, so this should only be used with a bounded set of names (such as
+/// the HTTP header names seen by a server).
+/// ```
+/// use flexstr::intern_header;
+///
+/// let h1 = intern_header("Access-Control-Allow-Credentials");
+/// let h2 = intern_header("access-control-allow-credentials");
+/// assert_eq!(h1, "access-control-allow-credentials");
+/// assert_eq!(h1.as_ptr(), h2.as_ptr());
+///
+/// assert!(intern_header("Host").is_inline());
+/// ```
+#[cfg(feature = "std")]
+pub fn intern_header(s: &str) -> SharedStr {
+    if s.len() <= STRING_SIZED_INLINE {
+        return s.to_ascii_lower();
+    }
+
+    let lower = match s.bytes().any(|b| b.is_ascii_uppercase()) {
+        true => Cow::Owned(s.to_ascii_lowercase()),
+        false => Cow::Borrowed(s),
+    };
+
+    // The pool is always left consistent, so a panic while it was locked can be ignored
+    let mut pool = header_pool()
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    match pool.get(&*lower) {
+        Some(existing) => existing.clone(),
+        None => {
+            let new = SharedStr::from_ref(&*lower);
+            pool.insert(new.clone());
+            new
+        }
+    }
+}
+
+/// Returns the global pool used by [intern_header], creating it on first use
+#[cfg(feature = "std")]
+fn header_pool() -> &'static std::sync::Mutex<BTreeSet<SharedStr>> {
+    use core::sync::atomic::{AtomicPtr, Ordering};
+
+    static POOL: AtomicPtr<std::sync::Mutex<BTreeSet<SharedStr>>> = AtomicPtr::new(ptr::null_mut());
+
+    let mut pool = POOL.load(Ordering::Acquire);
+
+    if pool.is_null() {
+        let new = Box::into_raw(Box::new(std::sync::Mutex::new(BTreeSet::new())));
+
+        pool = match POOL.compare_exchange(
+            ptr::null_mut(),
+            new,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => new,
+            Err(existing) => {
+                // SAFETY: Another thread won the race, so our pool was never published
+                drop(unsafe { Box::from_raw(new) });
+                existing
+            }
+        };
+    }
+
+    // SAFETY: Once published, the pool is never freed
+    unsafe { &*pool }
+}
+
+// *** Owned char iterator ***
+
+/// An iterator over the chars of a [FlexStr] that owns the string. This is returned by the
+/// [IntoIterator] implementation of [FlexStr] and, unlike [chars](str::chars), can outlive the
+/// original binding.
+pub struct IntoChars<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> {
+    s: FlexStr<SIZE, PAD1, PAD2, HEAP>,
+    start: usize,
+    end: usize,
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
+    IntoChars<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    #[inline]
+    pub(crate) fn new(s: FlexStr<SIZE, PAD1, PAD2, HEAP>) -> Self {
+        let end = s.len();
+        Self { s, start: 0, end }
+    }
+
+    /// Returns the chars not yet iterated as a string slice
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.s[self.start..self.end]
+    }
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> Iterator
+    for IntoChars<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let ch = self.as_str().chars().next()?;
+        self.start += ch.len_utf8();
+        Some(ch)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.as_str().chars().size_hint()
+    }
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> DoubleEndedIterator
+    for IntoChars<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let ch = self.as_str().chars().next_back()?;
+        self.end -= ch.len_utf8();
+        Some(ch)
+    }
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> FusedIterator
+    for IntoChars<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+}
+
+// *** Lazy concatenation ***
+
+/// A lazy concatenation of strings that can be written (via [Display](core::fmt::Display)) without first
+/// building the concatenated string in memory. This is useful when a string is built from many
+/// pieces only to be written to a formatter or other writer and then discarded. If needed, the
+/// pieces can still be concatenated into a single string via [into_flex](IntoFlex::into_flex).
+/// ```
+/// use flexstr::{local_str, FlexDisplay, IntoFlex, LocalStr};
+///
+/// let mut pieces = FlexDisplay::new();
+/// pieces.push(local_str!("Hello"));
+/// pieces.push_static(", ");
+/// pieces.push("world!".into());
+/// assert_eq!(format!("{}", pieces), "Hello, world!");
+///
+/// let s: LocalStr = pieces.into_flex();
+/// assert_eq!(s, "Hello, world!");
+/// ```
+pub struct FlexDisplay<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> {
+    pieces: Vec<FlexStr<SIZE, PAD1, PAD2, HEAP>>,
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
+    FlexDisplay<SIZE, PAD1, PAD2, HEAP>
+{
+    /// Creates a new empty lazy concatenation
+    #[inline]
+    pub fn new() -> Self {
+        Self { pieces: Vec::new() }
+    }
+
+    /// Appends `piece` to the end of the concatenation
+    #[inline]
+    pub fn push(&mut self, piece: FlexStr<SIZE, PAD1, PAD2, HEAP>) {
+        self.pieces.push(piece);
+    }
+
+    /// Appends a string literal to the end of the concatenation
+    #[inline]
+    pub fn push_static(&mut self, piece: &'static str) {
+        self.pieces.push(FlexStr::from_static(piece));
+    }
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
+    FlexDisplay<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    /// Returns the total length in bytes of all the pieces
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pieces.iter().map(|piece| piece.len()).sum()
+    }
+
+    /// Returns true if all pieces are empty (or there are none)
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pieces.iter().all(|piece| piece.is_empty())
+    }
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> Default
+    for FlexDisplay<SIZE, PAD1, PAD2, HEAP>
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> core::fmt::Display
+    for FlexDisplay<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.pieces.iter().try_for_each(|piece| f.write_str(piece))
+    }
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP, HEAP2>
+    IntoFlex<SIZE, PAD1, PAD2, HEAP> for FlexDisplay<SIZE, PAD1, PAD2, HEAP2>
+where
+    HEAP: for<'a> From<&'a str>,
+    HEAP2: Deref<Target = str>,
+{
+    /// Concatenates the pieces into a single string. A single piece is converted directly without
+    /// copying.
+    #[inline]
+    fn into_flex(mut self) -> FlexStr<SIZE, PAD1, PAD2, HEAP> {
+        match self.pieces.len() {
+            1 => self.pieces.remove(0).into_flex(),
+            _ => FlexStr::concat_from_iter(self.pieces.iter()),
+        }
+    }
+}
+
+// *** Growable string ***
+
+/// A growable string that appends in amortized O(1) time (like [String]) and is finished into a
+/// [FlexStr] once complete. This avoids the reallocation of each step of a chain of `+`
+/// operations when building a large string from many pieces. The finished string is inlined if
+/// short enough, otherwise heap allocated.
+/// ```
+/// use flexstr::{local_str, FlexString, LocalStr};
+///
+/// let mut s = FlexString::new();
+/// s.push_flex(&local_str!("Hello"));
+/// s.push_str(", ");
+/// s.push('w');
+/// s.push_str("orld!");
+/// assert_eq!(s.as_str(), "Hello, world!");
+///
+/// let s: LocalStr = s.finish();
+/// assert!(s.is_inline());
+/// assert_eq!(s, "Hello, world!");
+/// ```
+pub struct FlexString<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> {
+    buffer: String,
+    marker: core::marker::PhantomData<HEAP>,
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
+    FlexString<SIZE, PAD1, PAD2, HEAP>
+{
+    /// Creates a new empty growable string
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates a new empty growable string with at least `capacity` bytes of capacity
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buffer: String::with_capacity(capacity),
+            marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Appends `s` to the end of this string
+    #[inline]
+    pub fn push_flex(&mut self, s: &FlexStr<SIZE, PAD1, PAD2, HEAP>)
+    where
+        HEAP: Deref<Target = str>,
+    {
+        self.buffer.push_str(s);
+    }
+
+    /// Appends `s` to the end of this string
+    #[inline]
+    pub fn push_str(&mut self, s: &str) {
+        self.buffer.push_str(s);
+    }
+
+    /// Appends `ch` to the end of this string
+    #[inline]
+    pub fn push(&mut self, ch: char) {
+        self.buffer.push(ch);
+    }
+
+    /// Returns the string built so far
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Returns the length in bytes of the string built so far
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns true if nothing (or only empty strings) have been appended
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Returns the capacity in bytes of the underlying buffer
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more bytes, as with [reserve](String::reserve)
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.buffer.reserve(additional);
+    }
+
+    /// Shrinks the capacity of the underlying buffer to match its length, as with
+    /// [shrink_to_fit](String::shrink_to_fit)
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.buffer.shrink_to_fit();
+    }
+
+    /// Shrinks the capacity of the underlying buffer with a lower bound, as with
+    /// [shrink_to](String::shrink_to)
+    /// ```
+    /// use flexstr::{FlexString, LocalStr};
+    ///
+    /// let mut s = FlexString::with_capacity(100);
+    /// s.push_str("This is too long to be inlined");
+    /// s.shrink_to(40);
+    /// assert!(s.capacity() >= 40 && s.capacity() < 100);
+    ///
+    /// let s: LocalStr = s.finish();
+    /// assert_eq!(s.len(), 30);
+    /// ```
+    #[inline]
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.buffer.shrink_to(min_capacity);
+    }
+
+    /// Consumes this growable string, returning a [FlexStr] in the optimal storage for its length.
+    /// Heap storage is always exact sized, regardless of the capacity of this string.
+    #[inline]
+    pub fn finish(self) -> FlexStr<SIZE, PAD1, PAD2, HEAP>
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        FlexStr::from_ref(&self.buffer)
+    }
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> Default
+    for FlexString<SIZE, PAD1, PAD2, HEAP>
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> Write
+    for FlexString<SIZE, PAD1, PAD2, HEAP>
+{
+    #[inline]
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+// *** Streaming UTF-8 decoding ***
+
+/// An incremental UTF-8 decoder that turns a stream of byte chunks (such as network reads) into
+/// strings, where chunk boundaries are not required to fall on char boundaries. An incomplete char
+/// at the end of a chunk is held back and completed by the next chunk. The caller chooses how
+/// invalid UTF-8 is handled on each call: [push](Utf8Accumulator::push) returns an error, while
+/// [push_lossy](Utf8Accumulator::push_lossy) substitutes
+/// [REPLACEMENT_CHARACTER](char::REPLACEMENT_CHARACTER) (U+FFFD), the same as
+/// [from_utf8_lossy](String::from_utf8_lossy).
+/// ```
+/// use flexstr::{LocalStr, Utf8Accumulator};
+///
+/// let snowman = "☃".as_bytes();
+/// let mut acc = Utf8Accumulator::new();
+///
+/// let s: LocalStr = acc.push(&[b'a', snowman[0]]).unwrap();
+/// assert_eq!(s, "a");
+/// let s: LocalStr = acc.push(&snowman[1..]).unwrap();
+/// assert_eq!(s, "☃");
+/// assert!(acc.finish().is_ok());
+///
+/// // A 4 byte char split over three chunks
+/// let crab = "🦀".as_bytes();
+/// let s: LocalStr = acc.push(&crab[..1]).unwrap();
+/// assert!(s.is_empty() && acc.has_pending());
+/// let s: LocalStr = acc.push(&crab[1..2]).unwrap();
+/// assert!(s.is_empty());
+/// let s: LocalStr = acc.push(&crab[2..]).unwrap();
+/// assert_eq!(s, "🦀");
+///
+/// let s: LocalStr = acc.push_lossy(b"bad \xFF byte");
+/// assert_eq!(s, "bad \u{FFFD} byte");
+/// let s: LocalStr = acc.push_lossy(&crab[..2]);
+/// let s2: LocalStr = acc.push_lossy(b"!");
+/// assert_eq!((s, s2), ("".into(), "\u{FFFD}!".into()));
+/// let err: Result<LocalStr, _> = acc.push(b"\xFF");
+/// assert!(err.is_err());
+/// let s: LocalStr = acc.push_lossy(&snowman[..2]);
+/// assert!(s.is_empty());
+/// assert!(acc.finish().is_err());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Utf8Accumulator {
+    // An incomplete char (always a valid prefix of a char) held back from the last chunk
+    pending: [u8; 4],
+    pending_len: usize,
+}
+
+impl Utf8Accumulator {
+    /// Creates a new accumulator with no held back bytes
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if an incomplete char from a previous chunk is being held back
+    #[inline]
+    pub fn has_pending(&self) -> bool {
+        self.pending_len > 0
+    }
+
+    /// Decodes `chunk` (after any bytes held back from the previous chunk) and returns all the
+    /// complete chars as a new string. If the bytes are not valid UTF-8, an error is returned,
+    /// the output for this chunk is discarded and any held back bytes are cleared.
+    #[inline]
+    pub fn push<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>(
+        &mut self,
+        chunk: &[u8],
+    ) -> Result<FlexStr<SIZE, PAD1, PAD2, HEAP>, Utf8Error>
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        let result = self.decode(chunk, false);
+        if result.is_err() {
+            self.pending_len = 0;
+        }
+        result
+    }
+
+    /// Decodes `chunk` (after any bytes held back from the previous chunk) and returns all the
+    /// complete chars as a new string, replacing each invalid sequence with U+FFFD.
+    #[inline]
+    pub fn push_lossy<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>(
+        &mut self,
+        chunk: &[u8],
+    ) -> FlexStr<SIZE, PAD1, PAD2, HEAP>
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        // SAFETY: Errors are only returned when not lossy
+        unsafe { self.decode(chunk, true).unwrap_unchecked() }
+    }
+
+    /// Ends the stream, returning an error if an incomplete char is being held back. The
+    /// accumulator is reset and can be reused for a new stream.
+    #[inline]
+    pub fn finish(&mut self) -> Result<(), Utf8Error> {
+        let pending = &self.pending[..mem::take(&mut self.pending_len)];
+        core::str::from_utf8(pending).map(|_| ())
+    }
+
+    /// Ends the stream, returning U+FFFD if an incomplete char is being held back (otherwise an
+    /// empty string). The accumulator is reset and can be reused for a new stream.
+    #[inline]
+    pub fn finish_lossy<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>(
+        &mut self,
+    ) -> FlexStr<SIZE, PAD1, PAD2, HEAP> {
+        match self.finish() {
+            Ok(()) => FlexStr::from_static(""),
+            Err(_) => FlexStr::from_static("\u{FFFD}"),
+        }
+    }
+
+    fn decode<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>(
+        &mut self,
+        chunk: &[u8],
+        lossy: bool,
+    ) -> Result<FlexStr<SIZE, PAD1, PAD2, HEAP>, Utf8Error>
+    where
+        HEAP: for<'a> From<&'a str>,
+    {
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, self.pending_len + chunk.len());
+        let mut rest = chunk;
+
+        // First complete the held back char using only as many bytes as it needs, so a char that
+        // starts later in the chunk is never split
+        if self.pending_len > 0 {
+            let pending_len = mem::take(&mut self.pending_len);
+            let width = (self.pending[0].leading_ones() as usize).max(1);
+            let take = (width - pending_len).min(chunk.len());
+
+            let mut head = [0; 4];
+            head[..pending_len].copy_from_slice(&self.pending[..pending_len]);
+            head[pending_len..pending_len + take].copy_from_slice(&chunk[..take]);
+            let head = &head[..pending_len + take];
+
+            match core::str::from_utf8(head) {
+                Ok(s) => {
+                    builder.str_write(s);
+                    rest = &chunk[take..];
+                }
+                Err(err) => match err.error_len() {
+                    // Still incomplete - the whole chunk was used and more is needed
+                    None => {
+                        self.pending[..head.len()].copy_from_slice(head);
+                        self.pending_len = head.len();
+                        rest = &[];
+                    }
+                    // The invalid sequence always includes all the held back bytes
+                    Some(invalid) if lossy => {
+                        builder.char_write(char::REPLACEMENT_CHARACTER);
+                        rest = &chunk[invalid - pending_len..];
+                    }
+                    Some(_) => return Err(err),
+                },
+            }
+        }
+
+        while !rest.is_empty() {
+            match core::str::from_utf8(rest) {
+                Ok(s) => {
+                    builder.str_write(s);
+                    break;
+                }
+                Err(err) => {
+                    let valid = err.valid_up_to();
+                    // SAFETY: These bytes were just validated as UTF-8
+                    builder.str_write(unsafe { core::str::from_utf8_unchecked(&rest[..valid]) });
+
+                    match err.error_len() {
+                        None => {
+                            let incomplete = &rest[valid..];
+                            self.pending[..incomplete.len()].copy_from_slice(incomplete);
+                            self.pending_len = incomplete.len();
+                            break;
+                        }
+                        Some(invalid) if lossy => {
+                            builder.char_write(char::REPLACEMENT_CHARACTER);
+                            rest = &rest[valid + invalid..];
+                        }
+                        Some(_) => return Err(err),
+                    }
+                }
+            }
+        }
+
+        Ok(builder_into!(builder, buffer))
+    }
+}
+
+// *** Case insensitive ordering ***
+
+/// A wrapper that compares, orders and hashes the wrapped string ignoring ASCII case. It allows a
+/// collection of strings to be sorted (or used as map keys) case-insensitively without allocating
+/// lowercased copies. Strings that differ only in ASCII case are considered equal.
+/// ```
+/// use flexstr::{local_str, CaseInsensitiveOrd, LocalStr};
+///
+/// let mut v: Vec<LocalStr> = vec![local_str!("b"), local_str!("C"), local_str!("a")];
+/// v.sort_by(|s1, s2| CaseInsensitiveOrd(s1).cmp(&CaseInsensitiveOrd(s2)));
+/// assert_eq!(v, ["a", "b", "C"]);
+///
+/// assert_eq!(CaseInsensitiveOrd(local_str!("Host")), CaseInsensitiveOrd("HOST"));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CaseInsensitiveOrd<S>(pub S);
+
+impl<S: AsRef<str>, S2: AsRef<str>> PartialEq<CaseInsensitiveOrd<S2>> for CaseInsensitiveOrd<S> {
+    #[inline]
+    fn eq(&self, other: &CaseInsensitiveOrd<S2>) -> bool {
+        self.0.as_ref().eq_ignore_ascii_case(other.0.as_ref())
+    }
+}
+
+impl<S: AsRef<str>> Eq for CaseInsensitiveOrd<S> {}
+
+impl<S: AsRef<str>, S2: AsRef<str>> PartialOrd<CaseInsensitiveOrd<S2>> for CaseInsensitiveOrd<S> {
+    #[inline]
+    fn partial_cmp(&self, other: &CaseInsensitiveOrd<S2>) -> Option<Ordering> {
+        Some(cmp_ignore_ascii_case(self.0.as_ref(), other.0.as_ref()))
+    }
+}
+
+impl<S: AsRef<str>> Ord for CaseInsensitiveOrd<S> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_ignore_ascii_case(self.0.as_ref(), other.0.as_ref())
+    }
+}
+
+impl<S: AsRef<str>> Hash for CaseInsensitiveOrd<S> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let s = self.0.as_ref();
+        for b in s.bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+        // Same terminator as `str` to prevent prefix collisions
+        state.write_u8(0xff);
+    }
+}
+
+#[inline]
+fn cmp_ignore_ascii_case(s1: &str, s2: &str) -> Ordering {
+    let s1 = s1.bytes().map(|b| b.to_ascii_lowercase());
+    let s2 = s2.bytes().map(|b| b.to_ascii_lowercase());
+    s1.cmp(s2)
+}
+
+// *** Search key ***
+
+/// A wrapper that compares, orders and hashes the wrapped string by its search form: the string is
+/// Unicode case folded, decomposed (NFD) and then stripped of combining marks. Strings that differ
+/// only by case and diacritics are considered equal, making this suitable as a map key for
+/// user-facing search. The search form is computed once on creation and stored alongside the
+/// original string.
+///
+/// Requires the `search_key` feature.
+/// ```
+/// use flexstr::{local_str, LocalSearchKey};
+/// use std::collections::HashMap;
+///
+/// let mut index = HashMap::new();
+/// index.insert(LocalSearchKey::new(local_str!("Crème Brûlée")), 1);
+/// assert_eq!(index.get(&LocalSearchKey::new(local_str!("CREME brulee"))), Some(&1));
+///
+/// let key = LocalSearchKey::new(local_str!("Ångström"));
+/// assert_eq!(key.as_str(), "Ångström");
+/// assert_eq!(key.search_form(), "angstrom");
+/// ```
+#[cfg(feature = "search_key")]
+#[derive(Clone)]
+pub struct SearchKey<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> {
+    string: FlexStr<SIZE, PAD1, PAD2, HEAP>,
+    search_form: FlexStr<SIZE, PAD1, PAD2, HEAP>,
+}
+
+/// A search key based on [LocalStr]
+#[cfg(feature = "search_key")]
+pub type LocalSearchKey = SearchKey<STRING_SIZED_INLINE, PTR_SIZED_PAD, PTR_SIZED_PAD, Rc<str>>;
+
+/// A search key based on [SharedStr]
+#[cfg(feature = "search_key")]
+pub type SharedSearchKey = SearchKey<STRING_SIZED_INLINE, PTR_SIZED_PAD, PTR_SIZED_PAD, Arc<str>>;
+
+#[cfg(feature = "search_key")]
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
+    SearchKey<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str> + for<'a> From<&'a str>,
+{
+    /// Creates a new search key from `string`, computing its search form
+    pub fn new(string: FlexStr<SIZE, PAD1, PAD2, HEAP>) -> Self {
+        // We estimate capacity based on the original string, but if not ASCII this might be wrong
+        let mut buffer = buffer_new!(SIZE);
+        let mut builder = builder_new!(buffer, string.len());
+
+        // Folding must come first as it can produce combining marks (for example, 'İ' -> "i̇")
+        for ch in string
+            .chars()
+            .default_case_fold()
+            .nfd()
+            .filter(|&ch| !is_combining_mark(ch))
+        {
+            builder.char_write(ch);
+        }
+
+        let search_form = builder_into!(builder, buffer);
+        Self {
+            string,
+            search_form,
+        }
+    }
+
+    /// Returns the original string
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.string
+    }
+
+    /// Returns the search form of the string that is used for comparison and hashing
+    #[inline]
+    pub fn search_form(&self) -> &str {
+        &self.search_form
+    }
+
+    /// Consumes this search key, returning the original string
+    #[inline]
+    pub fn into_inner(self) -> FlexStr<SIZE, PAD1, PAD2, HEAP> {
+        self.string
+    }
+}
+
+#[cfg(feature = "search_key")]
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
+    From<FlexStr<SIZE, PAD1, PAD2, HEAP>> for SearchKey<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str> + for<'a> From<&'a str>,
+{
+    #[inline]
+    fn from(string: FlexStr<SIZE, PAD1, PAD2, HEAP>) -> Self {
+        Self::new(string)
+    }
+}
+
+#[cfg(feature = "search_key")]
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> core::fmt::Debug
+    for SearchKey<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SearchKey")
+            .field("string", &self.string)
+            .field("search_form", &self.search_form)
+            .finish()
+    }
+}
+
+#[cfg(feature = "search_key")]
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> PartialEq
+    for SearchKey<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.search_form.as_str() == other.search_form.as_str()
+    }
+}
+
+#[cfg(feature = "search_key")]
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> Eq
+    for SearchKey<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+}
+
+#[cfg(feature = "search_key")]
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> PartialOrd
+    for SearchKey<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "search_key")]
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> Ord
+    for SearchKey<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.search_form.as_str().cmp(other.search_form.as_str())
+    }
+}
+
+#[cfg(feature = "search_key")]
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> Hash
+    for SearchKey<SIZE, PAD1, PAD2, HEAP>
+where
+    HEAP: Deref<Target = str>,
+{
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.search_form.as_str().hash(state);
+    }
 }
@@ -0,0 +1,107 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Formatter;
+use core::marker::PhantomData;
+
+use ::serde::de::{Error, Unexpected, Visitor};
+use ::serde::Deserializer;
+
+/// Deserializes a string, failing if it is longer than `MAX` bytes. The length is checked before
+/// the string is constructed, so oversized input is rejected without being copied. This is
+/// intended for use with serde's `deserialize_with` attribute when deserializing untrusted input.
+/// ```
+/// use flexstr::LocalStr;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     #[serde(deserialize_with = "flexstr::serde::bounded::<8, _, _>")]
+///     name: LocalStr,
+/// }
+///
+/// let user: User = serde_json::from_str(r#"{"name": "Scott"}"#).unwrap();
+/// assert_eq!(user.name, "Scott");
+/// assert!(serde_json::from_str::<User>(r#"{"name": "Scott Meeuwsen"}"#).is_err());
+/// ```
+pub fn bounded<'de, const MAX: usize, D, S>(deserializer: D) -> Result<S, D::Error>
+where
+    D: Deserializer<'de>,
+    S: for<'a> From<&'a str> + From<String>,
+{
+    deserializer.deserialize_str(BoundedVisitor::<MAX, S>(PhantomData))
+}
+
+// Uses *const T because we don't want it to actually own a `T`
+struct BoundedVisitor<const MAX: usize, S>(PhantomData<*const S>);
+
+impl<const MAX: usize, S> BoundedVisitor<MAX, S>
+where
+    S: for<'a> From<&'a str> + From<String>,
+{
+    #[inline]
+    fn check_len<E: Error>(&self, len: usize) -> Result<(), E> {
+        if len <= MAX {
+            Ok(())
+        } else {
+            Err(Error::invalid_length(len, self))
+        }
+    }
+}
+
+impl<'de, const MAX: usize, S> Visitor<'de> for BoundedVisitor<MAX, S>
+where
+    S: for<'a> From<&'a str> + From<String>,
+{
+    type Value = S;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a string of at most {} bytes", MAX)
+    }
+
+    #[inline]
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        self.check_len(v.len())?;
+        Ok(v.into())
+    }
+
+    #[inline]
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        self.check_len(v.len())?;
+        Ok(v.into())
+    }
+
+    #[inline]
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        self.check_len(v.len())?;
+        match core::str::from_utf8(v) {
+            Ok(s) => Ok(s.into()),
+            Err(_) => Err(Error::invalid_value(Unexpected::Bytes(v), &self)),
+        }
+    }
+
+    #[inline]
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        self.check_len(v.len())?;
+        match String::from_utf8(v) {
+            Ok(s) => Ok(s.into()),
+            Err(err) => Err(Error::invalid_value(
+                Unexpected::Bytes(err.as_bytes()),
+                &self,
+            )),
+        }
+    }
+}
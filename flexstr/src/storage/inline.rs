@@ -1,16 +1,31 @@
 use alloc::string::String;
-use core::fmt::{Debug, Formatter};
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt::{Debug, Display, Formatter};
+use core::hash::{Hash, Hasher};
 use core::ops::Deref;
 use core::{fmt, mem, ptr, str};
 
 use crate::storage::StorageType;
 
 /// Using this inline capacity will result in a type with the same memory size as a builtin [String]
+/// (22 bytes on 64-bit and 10 bytes on 32-bit platforms)
 pub const STRING_SIZED_INLINE: usize = mem::size_of::<String>() - 2;
 
-/// This is the custom inline string type - it is not typically used directly, but instead is used
-/// transparently by [LocalStr] and [SharedStr]
-#[doc(hidden)]
+/// This is the custom inline string type - it is typically used transparently by
+/// [LocalStr](crate::LocalStr) and [SharedStr](crate::SharedStr), but it can also be used directly
+/// when strings are known to be short (such as currency or country codes). It can never heap
+/// allocate, and in exchange, it is [Copy] and has no storage branches. Creation fails if the
+/// string is longer than the capacity `N` (which can be at most 255).
+/// ```
+/// use flexstr::InlineFlexStr;
+///
+/// let code: InlineFlexStr<3> = InlineFlexStr::try_new("USD").unwrap();
+/// let copy = code;
+/// assert_eq!(code, copy);
+/// assert_eq!(code, "USD");
+/// assert!(InlineFlexStr::<3>::try_new("EURO").is_err());
+/// ```
 #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
 #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
 #[derive(Clone, Copy)]
@@ -22,10 +37,15 @@ pub struct InlineFlexStr<const N: usize = STRING_SIZED_INLINE> {
 }
 
 impl<const N: usize> InlineFlexStr<N> {
+    // The length is stored in a `u8`, so larger capacities are rejected at compile time
+    const VALID_CAPACITY: () = assert!(N <= u8::MAX as usize, "inline capacity must be <= 255");
+
     /// Attempts to return a new `InlineFlexStr` if the source string is short enough to be copied.
     /// If not, the source is returned as the error.
     #[inline(always)]
     pub fn try_new<T: AsRef<str>>(s: T) -> Result<Self, T> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_CAPACITY;
         let s_ref = s.as_ref();
 
         if s_ref.len() <= Self::capacity() {
@@ -53,8 +73,17 @@ impl<const N: usize> InlineFlexStr<N> {
         }
     }
 
+    /// Creates a new `InlineFlexStr` from a raw buffer and the length of its contents.
+    ///
+    /// # Safety
+    /// `len` must not exceed `N`, and the first `len` bytes of `data` must be initialized and
+    /// contain valid UTF-8.
+    #[doc(hidden)]
     #[inline]
-    pub fn from_array(data: [mem::MaybeUninit<u8>; N], len: u8) -> Self {
+    pub unsafe fn from_array(data: [mem::MaybeUninit<u8>; N], len: u8) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_CAPACITY;
+
         Self {
             data,
             len,
@@ -120,6 +149,71 @@ impl<const N: usize> ufmt::uDebug for InlineFlexStr<N> {
     }
 }
 
+impl<const N: usize> Display for InlineFlexStr<N> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        <str as Display>::fmt(self, f)
+    }
+}
+
+impl<const N: usize> Hash for InlineFlexStr<N> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        str::hash(self, state)
+    }
+}
+
+impl<const N: usize> PartialEq for InlineFlexStr<N> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        str::eq(self, &**other)
+    }
+}
+
+impl<const N: usize> PartialEq<str> for InlineFlexStr<N> {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        str::eq(self, other)
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for InlineFlexStr<N> {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        str::eq(self, *other)
+    }
+}
+
+impl<const N: usize> Eq for InlineFlexStr<N> {}
+
+impl<const N: usize> PartialOrd for InlineFlexStr<N> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for InlineFlexStr<N> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        str::cmp(self, other)
+    }
+}
+
+impl<const N: usize> Borrow<str> for InlineFlexStr<N> {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl<const N: usize> AsRef<str> for InlineFlexStr<N> {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
 impl<const N: usize> Deref for InlineFlexStr<N> {
     type Target = str;
 
@@ -165,8 +259,47 @@ impl<'s, const N: usize> TryFrom<&'s str> for InlineFlexStr<N> {
 
 #[cfg(test)]
 mod tests {
-    use crate::storage::inline::InlineFlexStr;
-    use alloc::string::ToString;
+    use crate::storage::inline::{InlineFlexStr, STRING_SIZED_INLINE};
+    use crate::{LocalStr, SharedStr, PTR_SIZED_PAD};
+    use alloc::string::{String, ToString};
+    use core::mem;
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn layout_64_bit() {
+        assert_eq!(STRING_SIZED_INLINE, 22);
+        assert_eq!(PTR_SIZED_PAD, 7);
+        assert_eq!(LocalStr::inline_capacity(), 22);
+        assert_eq!(mem::size_of::<InlineFlexStr>(), 24);
+        assert_eq!(mem::size_of::<LocalStr>(), 24);
+        assert_eq!(mem::size_of::<SharedStr>(), 24);
+    }
+
+    #[cfg(target_pointer_width = "32")]
+    #[test]
+    fn layout_32_bit() {
+        assert_eq!(STRING_SIZED_INLINE, 10);
+        assert_eq!(PTR_SIZED_PAD, 3);
+        assert_eq!(LocalStr::inline_capacity(), 10);
+        assert_eq!(mem::size_of::<InlineFlexStr>(), 12);
+        assert_eq!(mem::size_of::<LocalStr>(), 12);
+        assert_eq!(mem::size_of::<SharedStr>(), 12);
+    }
+
+    #[test]
+    fn full_inline_capacity() {
+        // A string of exactly the inline capacity must round trip without corruption
+        let lit = "x".repeat(STRING_SIZED_INLINE);
+        let s: LocalStr = lit.as_str().into();
+        assert!(s.is_inline());
+        assert_eq!(s, lit);
+        assert_eq!(mem::size_of::<LocalStr>(), mem::size_of::<String>());
+
+        let lit = "x".repeat(STRING_SIZED_INLINE + 1);
+        let s: LocalStr = lit.as_str().into();
+        assert!(s.is_heap());
+        assert_eq!(s, lit);
+    }
 
     #[test]
     fn empty() {
@@ -30,6 +30,30 @@ impl Display for WrongStorageType {
 #[cfg(feature = "std")]
 impl std::error::Error for WrongStorageType {}
 
+// *** Capacity Error ***
+
+/// Error type returned when an operation would result in a string longer than the allowed capacity
+#[derive(Copy, Clone, Debug)]
+pub struct CapacityError {
+    /// The length in bytes the resulting string would have required
+    pub required: usize,
+    /// The maximum length in bytes that was allowed
+    pub capacity: usize,
+}
+
+impl Display for CapacityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The string would exceed the available capacity (required: {}, capacity: {})",
+            self.required, self.capacity
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CapacityError {}
+
 // *** Storage Type ***
 
 /// Represents the storage type used by a particular [FlexStr](crate::FlexStr)
@@ -43,3 +67,25 @@ pub enum StorageType {
     /// Denotes that this [FlexStr](crate::FlexStr) uses heap-based storage
     Heap,
 }
+
+// *** Storage Policy ***
+
+/// Represents the storage a caller would like used when creating a [FlexStr](crate::FlexStr) via
+/// [to_flex_with](crate::ToFlex::to_flex_with)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StoragePolicy {
+    /// Storage is chosen automatically, the same as [to_flex](crate::ToFlex::to_flex)
+    Auto,
+    /// Heap storage is used even when the string could be inlined (for example, when it will be
+    /// cloned across many threads)
+    PreferHeap,
+    /// Inline storage is used, and an error is returned if the string is too long to be inlined
+    InlineOnly,
+}
+
+impl Default for StoragePolicy {
+    #[inline]
+    fn default() -> Self {
+        StoragePolicy::Auto
+    }
+}
@@ -1,9 +1,11 @@
 use alloc::string::String;
+use core::iter;
 use core::mem::ManuallyDrop;
 use core::ops::Deref;
 
 use crate::{
-    FlexStr, HeapStr, LocalStr, SharedStr, StorageType, PTR_SIZED_PAD, STRING_SIZED_INLINE,
+    CapacityError, FlexStr, HeapStr, LocalStr, SharedStr, StoragePolicy, StorageType,
+    PTR_SIZED_PAD, STRING_SIZED_INLINE,
 };
 
 // *** Repeat custom trait ***
@@ -68,6 +70,8 @@ pub trait ToCase<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
     fn to_ascii_lower(&self) -> FlexStr<SIZE, PAD1, PAD2, HEAP>;
 }
 
+/// Unlike the implementation for [str], if a static string literal is already in the requested
+/// case, it is returned as is instead of building a new string.
 impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> ToCase<SIZE, PAD1, PAD2, HEAP>
     for FlexStr<SIZE, PAD1, PAD2, HEAP>
 where
@@ -78,10 +82,18 @@ where
     ///
     /// let a: LocalStr = local_str!("test").to_upper();
     /// assert_eq!(a, "TEST");
+    ///
+    /// let b: LocalStr = local_str!("TEST").to_upper();
+    /// assert!(b.is_static());
     /// ```
     #[inline]
     fn to_upper(&self) -> FlexStr<SIZE, PAD1, PAD2, HEAP> {
-        str::to_upper(self)
+        match self.try_as_static_str() {
+            Ok(s) if s.chars().all(|ch| ch.to_uppercase().eq(iter::once(ch))) => {
+                FlexStr::from_static(s)
+            }
+            _ => str::to_upper(self),
+        }
     }
 
     /// ```
@@ -89,10 +101,18 @@ where
     ///
     /// let a: LocalStr = local_str!("TEST").to_lower();
     /// assert_eq!(a, "test");
+    ///
+    /// let b: LocalStr = local_str!("test").to_lower();
+    /// assert!(b.is_static());
     /// ```
     #[inline]
     fn to_lower(&self) -> FlexStr<SIZE, PAD1, PAD2, HEAP> {
-        str::to_lower(self)
+        match self.try_as_static_str() {
+            Ok(s) if s.chars().all(|ch| ch.to_lowercase().eq(iter::once(ch))) => {
+                FlexStr::from_static(s)
+            }
+            _ => str::to_lower(self),
+        }
     }
 
     /// ```
@@ -100,10 +120,16 @@ where
     ///
     /// let a: LocalStr = local_str!("test").to_ascii_upper();
     /// assert_eq!(a, "TEST");
+    ///
+    /// let b: LocalStr = local_str!("TEST").to_ascii_upper();
+    /// assert!(b.is_static());
     /// ```
     #[inline]
     fn to_ascii_upper(&self) -> FlexStr<SIZE, PAD1, PAD2, HEAP> {
-        str::to_ascii_upper(self)
+        match self.try_as_static_str() {
+            Ok(s) if !s.bytes().any(|b| b.is_ascii_lowercase()) => FlexStr::from_static(s),
+            _ => str::to_ascii_upper(self),
+        }
     }
 
     /// ```
@@ -111,10 +137,16 @@ where
     ///
     /// let a: LocalStr = local_str!("TEST").to_ascii_lower();
     /// assert_eq!(a, "test");
+    ///
+    /// let b: LocalStr = local_str!("test").to_ascii_lower();
+    /// assert!(b.is_static());
     /// ```
     #[inline]
     fn to_ascii_lower(&self) -> FlexStr<SIZE, PAD1, PAD2, HEAP> {
-        str::to_ascii_lower(self)
+        match self.try_as_static_str() {
+            Ok(s) if !s.bytes().any(|b| b.is_ascii_uppercase()) => FlexStr::from_static(s),
+            _ => str::to_ascii_lower(self),
+        }
     }
 }
 
@@ -214,6 +246,42 @@ where
 pub trait ToFlex<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> {
     /// Converts the source to a [FlexStr] without consuming it
     fn to_flex(&self) -> FlexStr<SIZE, PAD1, PAD2, HEAP>;
+
+    /// Converts the source to a [FlexStr] without consuming it, using the storage requested by
+    /// `policy`. A [CapacityError] is returned only if [InlineOnly](StoragePolicy::InlineOnly) is
+    /// requested and the string is too long to be inlined.
+    /// ```
+    /// use flexstr::{LocalStr, StoragePolicy, ToFlex};
+    ///
+    /// let a: LocalStr = "short".to_flex_with(StoragePolicy::PreferHeap).unwrap();
+    /// assert!(a.is_heap());
+    /// let b: LocalStr = "short".to_flex_with(StoragePolicy::InlineOnly).unwrap();
+    /// assert!(b.is_inline());
+    ///
+    /// let long = "This is too long to be inlined!!!!";
+    /// let c: Result<LocalStr, _> = long.to_flex_with(StoragePolicy::InlineOnly);
+    /// assert!(c.is_err());
+    /// ```
+    fn to_flex_with(
+        &self,
+        policy: StoragePolicy,
+    ) -> Result<FlexStr<SIZE, PAD1, PAD2, HEAP>, CapacityError>
+    where
+        HEAP: Deref<Target = str> + for<'a> From<&'a str>,
+    {
+        let s = self.to_flex();
+
+        match policy {
+            StoragePolicy::Auto => Ok(s),
+            StoragePolicy::PreferHeap if s.is_heap() => Ok(s),
+            StoragePolicy::PreferHeap => Ok(FlexStr::from_ref_heap(&s)),
+            StoragePolicy::InlineOnly if s.is_inline() => Ok(s),
+            StoragePolicy::InlineOnly => FlexStr::try_inline(&s).map_err(|s| CapacityError {
+                required: s.len(),
+                capacity: SIZE,
+            }),
+        }
+    }
 }
 
 impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP, HEAP2>
@@ -233,6 +301,27 @@ where
     fn to_flex(&self) -> FlexStr<SIZE, PAD1, PAD2, HEAP> {
         self.clone().into_flex()
     }
+
+    #[inline]
+    fn to_flex_with(
+        &self,
+        policy: StoragePolicy,
+    ) -> Result<FlexStr<SIZE, PAD1, PAD2, HEAP>, CapacityError>
+    where
+        HEAP: Deref<Target = str> + for<'a> From<&'a str>,
+    {
+        match policy {
+            StoragePolicy::Auto => Ok(self.to_flex()),
+            StoragePolicy::PreferHeap if self.is_heap() => Ok(self.to_flex()),
+            StoragePolicy::PreferHeap => Ok(FlexStr::from_ref_heap(self)),
+            StoragePolicy::InlineOnly => {
+                FlexStr::try_inline(self.as_str()).map_err(|s| CapacityError {
+                    required: s.len(),
+                    capacity: SIZE,
+                })
+            }
+        }
+    }
 }
 
 impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> ToFlex<SIZE, PAD1, PAD2, HEAP>
@@ -254,6 +343,24 @@ where
     fn to_flex(&self) -> FlexStr<SIZE, PAD1, PAD2, HEAP> {
         self.into()
     }
+
+    #[inline]
+    fn to_flex_with(
+        &self,
+        policy: StoragePolicy,
+    ) -> Result<FlexStr<SIZE, PAD1, PAD2, HEAP>, CapacityError>
+    where
+        HEAP: Deref<Target = str> + for<'a> From<&'a str>,
+    {
+        match policy {
+            StoragePolicy::Auto => Ok(self.to_flex()),
+            StoragePolicy::PreferHeap => Ok(FlexStr::from_ref_heap(self)),
+            StoragePolicy::InlineOnly => FlexStr::try_inline(self).map_err(|s| CapacityError {
+                required: s.len(),
+                capacity: SIZE,
+            }),
+        }
+    }
 }
 
 impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> ToFlex<SIZE, PAD1, PAD2, HEAP>
@@ -311,6 +418,18 @@ macro_rules! impl_int_flex {
                 let mut buffer = itoa::Buffer::new();
                 buffer.format(*self).to_flex()
             }
+
+            #[inline]
+            fn to_flex_with(
+                &self,
+                policy: StoragePolicy,
+            ) -> Result<FlexStr<SIZE, PAD1, PAD2, HEAP>, CapacityError>
+            where
+                HEAP: Deref<Target = str> + for<'a> From<&'a str>,
+            {
+                let mut buffer = itoa::Buffer::new();
+                buffer.format(*self).to_flex_with(policy)
+            }
         })+
     };
 }
@@ -337,6 +456,18 @@ macro_rules! impl_float_flex {
                 let mut buffer = ryu::Buffer::new();
                 buffer.format(*self).to_flex()
             }
+
+            #[inline]
+            fn to_flex_with(
+                &self,
+                policy: StoragePolicy,
+            ) -> Result<FlexStr<SIZE, PAD1, PAD2, HEAP>, CapacityError>
+            where
+                HEAP: Deref<Target = str> + for<'a> From<&'a str>,
+            {
+                let mut buffer = ryu::Buffer::new();
+                buffer.format(*self).to_flex_with(policy)
+            }
         })+
     };
 }
@@ -432,6 +563,41 @@ where
     }
 }
 
+// *** Generic `Drain` Trait ***
+
+/// A trait that drains a mutable buffer into a [FlexStr], leaving the buffer empty
+pub trait DrainFlex<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP> {
+    /// Converts the contents of this buffer into a [FlexStr] and leaves the buffer empty. The
+    /// buffer's capacity is retained so it can be reused to accumulate the next string
+    fn drain_to_flex(&mut self) -> FlexStr<SIZE, PAD1, PAD2, HEAP>;
+}
+
+impl<const SIZE: usize, const PAD1: usize, const PAD2: usize, HEAP>
+    DrainFlex<SIZE, PAD1, PAD2, HEAP> for String
+where
+    HEAP: for<'a> From<&'a str>,
+{
+    /// ```
+    /// use flexstr::{DrainFlex, SharedStr};
+    ///
+    /// let mut buffer = String::with_capacity(64);
+    /// buffer.push_str("Inlined!");
+    /// let a: SharedStr = buffer.drain_to_flex();
+    /// assert!(a.is_inline());
+    /// assert_eq!(a, "Inlined!");
+    /// assert!(buffer.is_empty());
+    /// assert!(buffer.capacity() >= 64);
+    /// ```
+    #[inline]
+    fn drain_to_flex(&mut self) -> FlexStr<SIZE, PAD1, PAD2, HEAP> {
+        // Heap storage is an exact-sized `Rc<str>`/`Arc<str>` which always copies, so clearing
+        // (instead of taking) the `String` lets its buffer be reused
+        let s = <FlexStr<SIZE, PAD1, PAD2, HEAP> as From<&str>>::from(self.as_str());
+        self.clear();
+        s
+    }
+}
+
 // *** FlexStr `To` Traits ***
 
 /// A trait that converts the source to a [LocalStr] without consuming it